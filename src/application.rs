@@ -91,20 +91,42 @@ impl Application {
             set_command!("case edit", "moderation", crate::commands::case::edit::run),
             set_command!("case last", "moderation", crate::commands::case::last::run),
             set_command!("case list", "moderation", crate::commands::case::list::run),
+            set_command!("case history", "moderation", crate::commands::case::history::run),
+            set_command!("case restore", "moderation", crate::commands::case::restore::run),
+            set_command!("case search", "moderation", crate::commands::case::search::run),
+            set_command!("case export", "moderation", crate::commands::case::export::run),
 
             set_command!("timeout", "moderation", crate::commands::moderation::execute::run),
             set_command!("kick", "moderation", crate::commands::moderation::execute::run),
             set_command!("mute", "moderation", crate::commands::moderation::execute::run),
             set_command!("warn", "moderation", crate::commands::moderation::execute::run),
             set_command!("ban", "moderation", crate::commands::moderation::execute::run),
+            set_command!("unban", "moderation", crate::commands::moderation::revert::run),
+            set_command!("unmute", "moderation", crate::commands::moderation::revert::run),
+            set_command!("untimeout", "moderation", crate::commands::moderation::revert::run),
 
             set_command!("mod-dash", "moderation", crate::commands::moderation::dash::run),
             set_command!("clear", "moderation", crate::commands::moderation::clear::run),
+            set_command!("temprole add", "moderation", crate::commands::moderation::temprole::run),
+
+            set_command!("schedule message", "moderation", crate::commands::tasks::schedule::run),
+            set_command!("schedule delete", "moderation", crate::commands::tasks::schedule::run),
+            set_command!("tasks list", "moderation", crate::commands::tasks::list::run),
+            set_command!("tasks cancel", "moderation", crate::commands::tasks::cancel::run),
+            set_command!("remind", "reminders", crate::commands::tasks::remind::run),
 
             set_command!("top week all", "top", crate::commands::top::all::run),
             set_command!("top day all", "top", crate::commands::top::all::run),
+            set_command!("top month all", "top", crate::commands::top::all::run),
+            set_command!("top alltime all", "top", crate::commands::top::all::run),
             set_command!("top week me", "top", crate::commands::top::me::run),
             set_command!("top day me", "top", crate::commands::top::me::run),
+            set_command!("top month me", "top", crate::commands::top::me::run),
+            set_command!("top alltime me", "top", crate::commands::top::me::run),
+
+            set_command!("level", "levels", crate::commands::levels::level::run),
+            set_command!("levels top", "levels", crate::commands::levels::top::run),
+            set_command!("levels sync", "levels", crate::commands::levels::sync::run),
 
             set_command!("setup", "settings", crate::commands::settings::setup::run)
         ]);
@@ -116,6 +138,30 @@ impl Application {
                 command: "case list".to_string(),
                 id: "cl".to_string()
             }),
+            ("cs".to_string(), Component {
+                options: vec![
+                    ("member".to_string(), ConvertableCommandOptionType::User),
+                    ("moderator".to_string(), ConvertableCommandOptionType::User),
+                    ("type".to_string(), ConvertableCommandOptionType::Integer),
+                    ("from".to_string(), ConvertableCommandOptionType::String),
+                    ("to".to_string(), ConvertableCommandOptionType::String),
+                    ("removed".to_string(), ConvertableCommandOptionType::Boolean),
+                    ("reason".to_string(), ConvertableCommandOptionType::String)
+                ],
+                values: vec![("page".to_string(), ConvertableCommandOptionType::Integer)],
+                command: "case search".to_string(),
+                id: "cs".to_string()
+            }),
+            // the period is taken from options, so every period shares this component
+            ("top".to_string(), Component {
+                options: vec![
+                    ("period".to_string(), ConvertableCommandOptionType::String),
+                    ("ago".to_string(), ConvertableCommandOptionType::Integer)
+                ],
+                values: vec![("page".to_string(), ConvertableCommandOptionType::Integer)],
+                command: "top week all".to_string(),
+                id: "top".to_string()
+            }),
             ("mod-panel".to_string(), Component {
                 options: vec![("action".to_string(), ConvertableCommandOptionType::String)],
                 values: vec![],
@@ -124,9 +170,11 @@ impl Application {
             })
         ]);
 
-        let modals = moderation_modal!(["warn", "kick", "ban"], ["timeout", "mute"]);
+        let modals = moderation_modal!(
+            ["warn", "kick", "ban", "unban", "unmute", "untimeout"], ["timeout", "mute"]
+        );
 
-        let slower_commands = vec!["kick", "mute", "warn", "ban", "clear", "case list"]
+        let slower_commands = ["kick", "mute", "warn", "ban", "unban", "unmute", "untimeout", "clear", "case list", "case search", "case export", "levels sync"]
                 .iter().map(|c| c.to_string()).collect();
 
         Self {
@@ -1,19 +1,128 @@
-use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
-use tokio::sync::Mutex;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use tokio::time::Instant;
 use twilight_http::Client;
 use twilight_model::channel::Message;
 use twilight_model::id::Id;
 use twilight_model::id::marker::{GuildMarker, UserMarker, ChannelMarker};
+use crate::context::Context;
+use crate::database::redis::RedisConnection;
 use crate::events::automod::actions::run_bucket_action;
 use crate::models::config::GuildConfig;
 use crate::models::config::automod::actions::{IncreaseBucket, IncreaseBucketAmount};
 
-pub type Bucket = Arc<Mutex<HashMap<BucketLocationKey, HashMap<String, u8>>>>;
+pub type Bucket = Arc<dyn BucketStorage>;
 
 type BucketLocationKey = [u8; 24];
 
+/// Number of `MemoryBucket::add` calls between removing expired entries from all windows
+const SWEEP_INTERVAL: usize = 1024;
+
+#[derive(Clone, Copy)]
+pub struct BucketLocation {
+    pub guild_id: Id<GuildMarker>,
+    pub channel_id: Option<Id<ChannelMarker>>,
+    pub user_id: Id<UserMarker>
+}
+
+#[async_trait]
+pub trait BucketStorage: Send + Sync {
+    /// Adds `amount` to the sliding window for `duration` seconds and returns the sum of active entries
+    async fn add(
+        &self,
+        location: BucketLocation,
+        name: String,
+        amount: u32,
+        duration: u16
+    ) -> Result<u64, ()>;
+}
+
+/// Times and amounts of recorded hits for every location and bucket key
+type MemoryWindows = DashMap<(BucketLocationKey, String), Vec<(Instant, u32)>>;
+
+/// Sliding window kept in the process memory, counts are lost on restart
+#[derive(Clone, Default)]
+pub struct MemoryBucket {
+    windows: Arc<MemoryWindows>,
+    calls: Arc<AtomicUsize>
+}
+
+impl MemoryBucket {
+    fn sweep(&self, now: Instant) {
+        self.windows.retain(|_, entries| {
+            entries.retain(|(expires_at, _)| *expires_at > now);
+            !entries.is_empty()
+        });
+    }
+}
+
+#[async_trait]
+impl BucketStorage for MemoryBucket {
+    async fn add(
+        &self,
+        location: BucketLocation,
+        name: String,
+        amount: u32,
+        duration: u16
+    ) -> Result<u64, ()> {
+        let key = create_key(location.guild_id, location.channel_id, location.user_id);
+        let now = Instant::now();
+
+        let count = {
+            let mut entries = self.windows.entry((key, name)).or_default();
+            entries.retain(|(expires_at, _)| *expires_at > now);
+            entries.push((now + Duration::from_secs(duration as u64), amount));
+            entries.iter().map(|(_, amount)| *amount as u64).sum()
+        };
+
+        if self.calls.fetch_add(1, Ordering::Relaxed) % SWEEP_INTERVAL == SWEEP_INTERVAL - 1 {
+            self.sweep(now);
+        }
+
+        Ok(count)
+    }
+}
+
+/// Sliding window stored in Redis, shared between processes and kept across restarts
+#[derive(Clone)]
+pub struct RedisBucket {
+    redis: RedisConnection
+}
+
+impl RedisBucket {
+    pub fn new(redis: RedisConnection) -> Self {
+        Self { redis }
+    }
+}
+
+#[async_trait]
+impl BucketStorage for RedisBucket {
+    async fn add(
+        &self,
+        location: BucketLocation,
+        name: String,
+        amount: u32,
+        duration: u16
+    ) -> Result<u64, ()> {
+        let path = format!(
+            "bucket.{}.{}.{}.{name}",
+            location.guild_id,
+            location.user_id,
+            location.channel_id.map(Id::get).unwrap_or(0)
+        );
+
+        let now = chrono::Utc::now().timestamp_millis();
+        let expires_at = now + (duration as i64) * 1000;
+
+        self.redis.add_to_window(
+            path, amount as u64, now, expires_at, rand::random()
+        ).await.map_err(|_| ())
+    }
+}
+
 // concat_bytes!() is unstable
 fn create_key(
     guild_id: Id<GuildMarker>,
@@ -28,101 +137,95 @@ fn create_key(
     ]
 }
 
+/// Returns true only for the increase which moved the count above the limit
+fn is_crossing_limit(count: u64, amount: u32, limit: u32) -> bool {
+    let limit = limit as u64;
+    count > limit && count.saturating_sub(amount as u64) <= limit
+}
+
 pub async fn incr(
     discord_http: Arc<Client>,
     message: Arc<Message>,
     guild_config: Arc<GuildConfig>,
-    bucket: Bucket,
+    context: Arc<Context>,
     data: IncreaseBucket
 ) {
     let guild_id = guild_config.guild_id;
     let user_id = message.author.id;
 
     let amount = match data.amount {
-        IncreaseBucketAmount::Stickers => u8::try_from(message.sticker_items.len()).unwrap_or(u8::MAX),
-        IncreaseBucketAmount::Attachments => u8::try_from(message.attachments.len()).unwrap_or(u8::MAX),
-        IncreaseBucketAmount::Mentions => u8::try_from(message.mentions.len()).unwrap_or(u8::MAX),
+        IncreaseBucketAmount::Stickers => u32::try_from(message.sticker_items.len()).unwrap_or(u32::MAX),
+        IncreaseBucketAmount::Attachments => u32::try_from(message.attachments.len()).unwrap_or(u32::MAX),
+        IncreaseBucketAmount::Mentions => u32::try_from(message.mentions.len()).unwrap_or(u32::MAX),
         IncreaseBucketAmount::Static(value) => value,
     };
 
+    if amount == 0 { return }
+
     let bucket_data = match guild_config.get_bucket_action(&data.key) {
         Some(data) => data,
         None => return
     };
 
-    let channel_id = data.per_channel.then_some(message.channel_id);
-    let key = create_key(guild_id, channel_id, user_id);
-
-    let count = update(&bucket, key, data.key.to_owned(), move |count| {
-        if let Some(value) = count.checked_add(amount) {
-            *count = value
-        }
-    }).await;
-
-    if count > bucket_data.limit {
-        for action in &bucket_data.actions {
-            let run = run_bucket_action(
-                action.action.to_owned(),
-                message.to_owned(),
-                discord_http.to_owned(),
-                guild_config.to_owned(),
-                bucket_data.reason.to_owned(),
-            );
-
-            if action.sync {
-                run.await.ok();
-            } else { tokio::spawn(run); }
-        }
-    }
-
-    tokio::time::sleep(Duration::from_secs(data.duration as u64)).await;
-
-    update(&bucket, key, data.key, move |count| {
-        if let Some(value) = count.checked_sub(amount) {
-            *count = value
-        }
-    }).await;
-
-}
-
-async fn update<T>(
-    bucket: &Bucket,
-    key: BucketLocationKey,
-    name: String,
-    f: T
-) -> u8 where T: Fn(&mut u8) {
-    let mut bucket = bucket.lock().await;
-
-    let count = bucket
-        .entry(key).or_insert_with(HashMap::new)
-        .entry(name).or_insert(0);
+    let location = BucketLocation {
+        guild_id,
+        channel_id: data.per_channel.then_some(message.channel_id),
+        user_id
+    };
 
-    f(count);
+    let count = match context.bucket.add(location, data.key, amount, data.duration).await {
+        Ok(count) => count,
+        Err(_) => return
+    };
 
-    count.to_owned()
+    if !is_crossing_limit(count, amount, bucket_data.limit) { return }
+
+    for action in &bucket_data.actions {
+        let run = run_bucket_action(
+            action.action.to_owned(),
+            message.to_owned(),
+            discord_http.to_owned(),
+            context.to_owned(),
+            guild_config.to_owned(),
+            bucket_data.reason.to_owned(),
+        );
+
+        if action.sync {
+            run.await.ok();
+        } else { tokio::spawn(run); }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Bucket, update, create_key};
+    use super::{BucketLocation, BucketStorage, MemoryBucket, is_crossing_limit};
     use twilight_model::id::Id;
 
     #[tokio::test]
-    async fn test_update() {
-        let bucket = Bucket::default();
-        let key = create_key(Id::new(1), None, Id::new(1));
-        let name = "test".to_string();
-
-        let count = update(&bucket, key, name.to_owned(), |count| {
-            *count = 1;
-        }).await;
-
-        assert_eq!(count, 1);
-
-        let count = update(&bucket, key, name, |count| {
-            *count = 3;
-        }).await;
+    async fn test_memory_bucket() {
+        let bucket = MemoryBucket::default();
+        let location = BucketLocation {
+            guild_id: Id::new(1),
+            channel_id: None,
+            user_id: Id::new(1)
+        };
+
+        assert_eq!(bucket.add(location, "test".to_string(), 2, 10).await, Ok(2));
+        assert_eq!(bucket.add(location, "test".to_string(), 300, 10).await, Ok(302));
+        assert_eq!(bucket.add(location, "other".to_string(), 1, 10).await, Ok(1));
+
+        let location = BucketLocation { channel_id: Some(Id::new(1)), ..location };
+        assert_eq!(bucket.add(location, "test".to_string(), 1, 0).await, Ok(1));
+        // the previous entry expired immediately
+        assert_eq!(bucket.add(location, "test".to_string(), 1, 10).await, Ok(1));
+    }
 
-        assert_eq!(count, 3);
+    #[test]
+    fn test_is_crossing_limit() {
+        assert!(!is_crossing_limit(5, 1, 5));
+        assert!(is_crossing_limit(6, 1, 5));
+        assert!(!is_crossing_limit(7, 1, 5));
+        assert!(is_crossing_limit(10, 10, 5));
+        assert!(!is_crossing_limit(0, 0, 0));
     }
-}
\ No newline at end of file
+}
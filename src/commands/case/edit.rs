@@ -8,6 +8,7 @@ use crate::commands::context::InteractionContext;
 use crate::commands::ResponseData;
 use crate::context::Context;
 use crate::{extract, get_required_option, get_option};
+use crate::models::case::CaseChange;
 use crate::models::config::GuildConfig;
 use crate::utils::errors::Error;
 
@@ -15,11 +16,11 @@ pub async fn run(
     interaction: InteractionContext,
     context: Arc<Context>,
     discord_http: Arc<Client>,
-    _: GuildConfig
+    config: GuildConfig
 ) -> ResponseData {
 
     extract!(interaction.orginal, member, guild_id);
-    extract!(member, user);
+    extract!(&member, user);
 
     let case_index = get_required_option!(
             interaction.options.get("number"), CommandOptionValue::Integer
@@ -33,20 +34,24 @@ pub async fn run(
         return Err(Error::from("Reason is too long"))
     }
 
-    let mut case = context.mongodb.cases.find_one(
+    let case = context.mongodb.cases.find_one(
         doc! { "guild_id": guild_id.to_string(), "index": case_index, "removed": false }, None
     ).await.map_err(Error::from)?.ok_or("There is no case with selected id")?;
 
-    if case.moderator_id != user.id {
+    if !config.moderation.can_manage_case(case.moderator_id, user.id, &member.roles) {
         return Err(Error::from("You can't edit cases created by someone else"))
     }
 
-    context.mongodb.cases.update_one(
-        doc! { "guild_id": guild_id.to_string(), "index": case_index, "removed": false },
-        doc! { "$set": {"reason": reason.to_owned() } }, None
-    ).await.map_err(Error::from)?;
+    let change = CaseChange::new(user.id, "reason", case.reason, Some(reason.to_owned()));
 
-    case.reason = Some(reason);
+    let case = context.mongodb.update_case(
+        discord_http.to_owned(),
+        &config,
+        *case_index,
+        false,
+        doc! { "reason": reason },
+        change
+    ).await?;
 
     Ok((InteractionResponseData {
         allowed_mentions: None,
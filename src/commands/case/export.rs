@@ -0,0 +1,74 @@
+use std::sync::Arc;
+use futures_util::TryStreamExt;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::channel::message::MessageFlags;
+use twilight_model::http::attachment::Attachment;
+use twilight_model::http::interaction::InteractionResponseData;
+use crate::commands::case::parse_date;
+use crate::commands::context::InteractionContext;
+use crate::commands::ResponseData;
+use crate::context::Context;
+use crate::{extract, get_option};
+use crate::models::config::GuildConfig;
+use crate::models::export::{CaseExporter, CaseExportFilter, ExportFormat};
+use crate::utils::errors::Error;
+
+/// Upload limit of Discord for servers without boosts
+const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    discord_http: Arc<Client>,
+    _: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id);
+
+    let format = get_option!(interaction.options.get("format"), CommandOptionValue::String)
+        .map(|format| ExportFormat::parse(format).ok_or("Unknown export format"))
+        .transpose()?
+        .unwrap_or(ExportFormat::Csv);
+
+    let filter = CaseExportFilter {
+        member_id: get_option!(interaction.options.get("member"), CommandOptionValue::User).copied(),
+        from: get_option!(interaction.options.get("from"), CommandOptionValue::String)
+            .map(|from| parse_date(from, false)).transpose()?,
+        to: get_option!(interaction.options.get("to"), CommandOptionValue::String)
+            .map(|to| parse_date(to, true)).transpose()?
+    };
+
+    let mut cases = context.mongodb.export_cases(guild_id, &filter).await?;
+    let mut exporter = CaseExporter::new(format, discord_http);
+
+    let mut content = format.start().to_string();
+    while let Some(case) = cases.try_next().await.map_err(Error::from)? {
+        content.push_str(&exporter.write(&case).await);
+
+        if content.len() > MAX_ATTACHMENT_SIZE {
+            return Err(Error::from("Export is too large to be sent as an attachment, narrow down the filters or use the API"))
+        }
+    }
+    content.push_str(format.end());
+
+    if exporter.written() == 0 {
+        return Err(Error::from("There are no cases matching these filters"))
+    }
+
+    let attachment = Attachment::from_bytes(
+        format!("cases-{guild_id}.{}", format.extension()), content.into_bytes(), 0
+    );
+
+    Ok((InteractionResponseData {
+        allowed_mentions: None,
+        attachments: Some(vec![attachment]),
+        choices: None,
+        components: None,
+        content: Some(format!("Exported cases: {}", exporter.written())),
+        custom_id: None,
+        embeds: None,
+        flags: Some(MessageFlags::EPHEMERAL),
+        title: None,
+        tts: None
+    }, None))
+}
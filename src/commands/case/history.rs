@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use mongodb::bson::doc;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use crate::commands::context::InteractionContext;
+use crate::commands::ResponseData;
+use crate::context::Context;
+use crate::{extract, get_required_option, get_option};
+use crate::models::config::GuildConfig;
+use crate::utils::embeds::EmbedBuilder;
+use crate::utils::errors::Error;
+
+const HISTORY_LIMIT: usize = 20;
+/// Characters of the embed description used by changes, the rest is left for the line with hidden changes
+const DESCRIPTION_BUDGET: usize = 4000;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    _: Arc<Client>,
+    _: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id);
+
+    let case_index = get_required_option!(
+        interaction.options.get("number"), CommandOptionValue::Integer
+    );
+
+    // removed cases are included, so it's visible who removed them
+    let case = context.mongodb.cases.find_one(
+        doc! { "guild_id": guild_id.to_string(), "index": case_index }, None
+    ).await.map_err(Error::from)?.ok_or("Cannot find case with selected id")?;
+
+    let description = if case.history.is_empty() {
+        "This case wasn't changed".to_string()
+    } else {
+        // the newest changes first, older ones wouldn't fit into the embed
+        let mut lines = vec![];
+        let mut length = 0;
+        for change in case.history.iter().rev().take(HISTORY_LIMIT) {
+            let line = change.describe();
+            let line_length = line.chars().count() + 1;
+            if length + line_length > DESCRIPTION_BUDGET { break }
+
+            length += line_length;
+            lines.push(line);
+        }
+
+        let hidden = case.history.len() - lines.len();
+        if hidden > 0 { lines.push(format!("…and {hidden} more")) }
+        lines.join("\n")
+    };
+
+    Ok((
+        EmbedBuilder::new()
+            .title(format!("Case #{} history", case.index))
+            .description(description)
+            .to_interaction_response_data(true),
+        None
+    ))
+}
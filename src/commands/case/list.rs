@@ -1,4 +1,4 @@
-use mongodb::bson::doc;
+use mongodb::bson::{doc, DateTime};
 use mongodb::options::FindOptions;
 use twilight_model::channel::message::{Embed, Component};
 use twilight_model::channel::message::component::{SelectMenuOption, ActionRow, SelectMenu};
@@ -14,7 +14,7 @@ use crate::commands::context::{InteractionContext, InteractionHelpers};
 use crate::commands::ResponseData;
 use crate::context::Context;
 use crate::{extract, get_option, get_required_option};
-use crate::models::case::Case;
+use crate::models::case::{Case, CaseActionType, DEFAULT_CASE_POINTS};
 use crate::models::config::GuildConfig;
 use crate::utils::avatars::get_avatar_url;
 use crate::utils::errors::Error;
@@ -28,7 +28,9 @@ struct ActionDocument {
 struct CountActions {
     #[serde(rename = "_id")]
     target: ActionDocument,
-    count: usize
+    count: usize,
+    active: usize,
+    points: u64
 }
 
 pub async fn run(
@@ -94,6 +96,20 @@ pub async fn run(
         return Err(Error::from("This user has no cases"))
     }
 
+    // cases without the expiration time never expire
+    let is_active = doc! {
+        "$gt": [{ "$ifNull": ["$expires_at", DateTime::MAX] }, DateTime::now()]
+    };
+
+    // cases created before points were stored, reverse cases don't count
+    let reverse_actions = (0..=u8::MAX)
+        .filter(|action| CaseActionType::from(*action).is_reverse())
+        .map(i32::from)
+        .collect::<Vec<i32>>();
+    let default_points = doc! {
+        "$cond": [{ "$in": ["$action", reverse_actions] }, 0_i64, DEFAULT_CASE_POINTS as i64]
+    };
+
     let mut count = context.mongodb.cases.aggregate(
         [
             doc! { "$match": filter },
@@ -101,7 +117,11 @@ pub async fn run(
                 "$group": {
                     "_id": { "action": "$action" },
                     "count": { "$sum": 1_u32 },
-                    "totalValue": { "$sum": "$count" }
+                    "totalValue": { "$sum": "$count" },
+                    "active": { "$sum": { "$cond": [is_active.to_owned(), 1_i32, 0_i32] } },
+                    "points": {
+                        "$sum": { "$cond": [is_active, { "$ifNull": ["$points", default_points] }, 0_i32] }
+                    }
                 }
             }
         ],
@@ -109,6 +129,7 @@ pub async fn run(
     ).await.map_err(Error::from)?;
 
     let mut total = 0;
+    let mut active_points = 0;
     let mut footer = vec![];
 
     while let Some(result) = count.next().await {
@@ -121,15 +142,19 @@ pub async fn run(
             }
         } else { total += result.count }
 
-        footer.push(format!("{}: {}", match result.target.action {
-            7 => "Mutes",
-            1 => "Warns",
-            4 => "Bans",
-            6 => "Kicks",
-            _ => "???"
-        }, result.count));
+        active_points += result.points;
+
+        let expired = result.count - result.active;
+        footer.push(format!(
+            "{}: {}{}",
+            CaseActionType::from(result.target.action).plural_name(),
+            result.active,
+            if expired > 0 { format!(" (+{expired} expired)") } else { "".to_string() }
+        ));
     }
 
+    footer.push(format!("Active points: {active_points}"));
+
     let author = if let Some(user) = user_data {
         let avatar = get_avatar_url(user.avatar, user.id);
         EmbedAuthor {
@@ -2,4 +2,38 @@ pub mod last;
 pub mod details;
 pub mod list;
 pub mod remove;
-pub mod edit;
\ No newline at end of file
+pub mod edit;
+pub mod history;
+pub mod restore;
+pub mod search;
+pub mod export;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use mongodb::bson::DateTime;
+use crate::utils::errors::Error;
+
+/// Parses `YYYY-MM-DD` date, `end_of_day` returns the start of the next day for exclusive ranges
+pub fn parse_date(value: &str, end_of_day: bool) -> Result<DateTime, Error> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date `{value}`, expected format is YYYY-MM-DD"))?;
+
+    let date = if end_of_day {
+        date.succ_opt().ok_or("Date is out of range")?
+    } else { date };
+
+    let datetime = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).ok_or("Date is out of range")?);
+    Ok(DateTime::from_millis(datetime.timestamp_millis()))
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::DateTime;
+    use crate::commands::case::parse_date;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2023-11-14", false).ok(), Some(DateTime::from_millis(1699920000000)));
+        assert_eq!(parse_date("2023-11-14", true).ok(), Some(DateTime::from_millis(1700006400000)));
+        assert!(parse_date("14.11.2023", false).is_err());
+    }
+}
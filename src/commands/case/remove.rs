@@ -7,7 +7,8 @@ use twilight_model::channel::message::MessageFlags;
 use crate::commands::context::InteractionContext;
 use crate::commands::ResponseData;
 use crate::context::Context;
-use crate::{get_required_option, get_option};
+use crate::{extract, get_required_option, get_option};
+use crate::models::case::CaseChange;
 use crate::models::config::GuildConfig;
 use crate::utils::errors::Error;
 
@@ -17,19 +18,30 @@ pub async fn run(
     discord_http: Arc<Client>,
     config: GuildConfig
 ) -> ResponseData {
+    extract!(interaction.orginal, member);
+    extract!(&member, user);
 
     let case_index = *get_required_option!(
         interaction.options.get("number"), CommandOptionValue::Integer
     );
 
-    let removed_case = context.mongodb.cases.find_one_and_update(
-        doc! {
-            "guild_id": config.guild_id.to_string(),
-            "index": case_index,
-            "removed": false
-        }, doc! { "$set": {"removed": true } }, None
+    let case = context.mongodb.cases.find_one(
+        doc! { "guild_id": config.guild_id.to_string(), "index": case_index, "removed": false }, None
     ).await.map_err(Error::from)?.ok_or("Cannot find case with selected id")?;
 
+    if !config.moderation.can_manage_case(case.moderator_id, user.id, &member.roles) {
+        return Err(Error::from("You can't remove cases created by someone else"))
+    }
+
+    let removed_case = context.mongodb.update_case(
+        discord_http.to_owned(),
+        &config,
+        case_index,
+        false,
+        doc! { "removed": true },
+        CaseChange::new(user.id, "removed", Some("false".to_string()), Some("true".to_string()))
+    ).await?;
+
     Ok((InteractionResponseData {
         allowed_mentions: None,
         attachments: None,
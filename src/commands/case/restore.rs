@@ -0,0 +1,58 @@
+use std::sync::Arc;
+use mongodb::bson::doc;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::channel::message::MessageFlags;
+use crate::commands::context::InteractionContext;
+use crate::commands::ResponseData;
+use crate::context::Context;
+use crate::{extract, get_required_option, get_option};
+use crate::models::case::CaseChange;
+use crate::models::config::GuildConfig;
+use crate::utils::errors::Error;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    discord_http: Arc<Client>,
+    config: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, member);
+    extract!(&member, user);
+
+    let case_index = *get_required_option!(
+        interaction.options.get("number"), CommandOptionValue::Integer
+    );
+
+    let case = context.mongodb.cases.find_one(
+        doc! { "guild_id": config.guild_id.to_string(), "index": case_index, "removed": true }, None
+    ).await.map_err(Error::from)?.ok_or("Cannot find removed case with selected id")?;
+
+    if !config.moderation.can_manage_case(case.moderator_id, user.id, &member.roles) {
+        return Err(Error::from("You can't restore cases created by someone else"))
+    }
+
+    let restored_case = context.mongodb.update_case(
+        discord_http.to_owned(),
+        &config,
+        case_index,
+        true,
+        doc! { "removed": false },
+        CaseChange::new(user.id, "removed", Some("true".to_string()), Some("false".to_string()))
+    ).await?;
+
+    Ok((InteractionResponseData {
+        allowed_mentions: None,
+        attachments: None,
+        choices: None,
+        components: None,
+        content: Some("**Restored case**".to_string()),
+        custom_id: None,
+        embeds: Some(vec![restored_case.to_embed(discord_http).await?]),
+        flags: Some(MessageFlags::EPHEMERAL),
+        title: None,
+        tts: None
+    }, None))
+
+}
\ No newline at end of file
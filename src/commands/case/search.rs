@@ -0,0 +1,229 @@
+use std::sync::Arc;
+use futures_util::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::FindOptions;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::channel::message::{Component, Embed};
+use twilight_model::channel::message::component::{ActionRow, SelectMenu, SelectMenuOption};
+use twilight_model::channel::message::embed::EmbedFooter;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::id::Id;
+use twilight_model::id::marker::{GuildMarker, UserMarker};
+use crate::commands::case::parse_date;
+use crate::commands::context::InteractionContext;
+use crate::commands::ResponseData;
+use crate::context::Context;
+use crate::{extract, get_option};
+use crate::models::case::{Case, CaseActionType};
+use crate::models::config::GuildConfig;
+use crate::utils::errors::Error;
+
+const PAGE_SIZE: u64 = 6;
+const MAX_CUSTOM_ID_LENGTH: usize = 100;
+
+struct SearchFilters {
+    member_id: Option<Id<UserMarker>>,
+    moderator_id: Option<Id<UserMarker>>,
+    action: Option<u8>,
+    from: Option<String>,
+    to: Option<String>,
+    reason: Option<String>,
+    removed: bool
+}
+
+impl SearchFilters {
+    fn from_interaction(interaction: &InteractionContext) -> Result<Self, Error> {
+        let action = get_option!(
+            interaction.options.get("type"), CommandOptionValue::Integer
+        ).map(|action| {
+            let action = u8::try_from(*action).map_err(|_| "Unknown action type")?;
+            match CaseActionType::from(action) {
+                CaseActionType::Unknown(_) => Err("Unknown action type"),
+                _ => Ok(action)
+            }
+        }).transpose()?;
+
+        Ok(Self {
+            member_id: get_option!(interaction.options.get("member"), CommandOptionValue::User).copied(),
+            moderator_id: get_option!(interaction.options.get("moderator"), CommandOptionValue::User).copied(),
+            action,
+            from: get_option!(interaction.options.get("from"), CommandOptionValue::String).cloned(),
+            to: get_option!(interaction.options.get("to"), CommandOptionValue::String).cloned(),
+            reason: get_option!(interaction.options.get("reason"), CommandOptionValue::String).cloned(),
+            removed: get_option!(
+                interaction.options.get("removed"), CommandOptionValue::Boolean
+            ).copied().unwrap_or(false)
+        })
+    }
+
+    fn to_document(&self, guild_id: Id<GuildMarker>) -> Result<Document, Error> {
+        let mut filter = doc! { "guild_id": guild_id.to_string() };
+
+        if !self.removed { filter.insert("removed", false); }
+        if let Some(member_id) = self.member_id {
+            filter.insert("member_id", member_id.to_string());
+        }
+        if let Some(moderator_id) = self.moderator_id {
+            filter.insert("moderator_id", moderator_id.to_string());
+        }
+        if let Some(action) = self.action {
+            filter.insert("action", action as i64);
+        }
+
+        let mut created_at = Document::new();
+        if let Some(from) = &self.from {
+            created_at.insert("$gte", parse_date(from, false)?);
+        }
+        if let Some(to) = &self.to {
+            created_at.insert("$lt", parse_date(to, true)?);
+        }
+        if !created_at.is_empty() { filter.insert("created_at", created_at); }
+
+        if let Some(reason) = &self.reason {
+            filter.insert("reason", doc! { "$regex": escape_regex(reason), "$options": "i" });
+        }
+
+        Ok(filter)
+    }
+
+    /// Options of the `cs` component, `None` when filters don't fit into the custom id
+    fn to_custom_id(&self) -> Option<String> {
+        let reason = self.reason.to_owned().unwrap_or_default();
+        if reason.contains(':') { return None }
+
+        let custom_id = format!(
+            "a:cs:{}:{}:{}:{}:{}:{}:{reason}",
+            self.member_id.map(|id| id.to_string()).unwrap_or_default(),
+            self.moderator_id.map(|id| id.to_string()).unwrap_or_default(),
+            self.action.map(|action| action.to_string()).unwrap_or_default(),
+            self.from.to_owned().unwrap_or_default(),
+            self.to.to_owned().unwrap_or_default(),
+            if self.removed { "true" } else { "" }
+        );
+
+        (custom_id.len() <= MAX_CUSTOM_ID_LENGTH).then_some(custom_id)
+    }
+}
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    _: Arc<Client>,
+    _: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id);
+
+    let page = u64::try_from(
+        get_option!(
+            interaction.options.get("page"), CommandOptionValue::Integer
+        ).copied().unwrap_or(1)
+    ).map_err(|_| "Page must be u64")?.max(1);
+
+    let filters = SearchFilters::from_interaction(&interaction)?;
+    let filter = filters.to_document(guild_id)?;
+
+    let total = context.mongodb.cases.count_documents(filter.clone(), None)
+        .await.map_err(Error::from)?;
+
+    let case_list = context.mongodb.cases.find(
+        filter,
+        FindOptions::builder()
+            .limit(PAGE_SIZE as i64).skip(Some((page - 1) * PAGE_SIZE))
+            .sort(doc! { "created_at": -1_i32 }).build()
+    ).await.map_err(Error::from)?;
+
+    let case_list: Vec<Case> = case_list.try_collect().await.map_err(Error::from)?;
+
+    if case_list.is_empty() {
+        return Err(Error::from("There are no cases matching these filters on this page"))
+    }
+
+    let pages = if total % PAGE_SIZE == 0 { total / PAGE_SIZE } else { total / PAGE_SIZE + 1 };
+
+    let fields = case_list.into_iter().map(|case| {
+        let mut field = case.to_field();
+        if case.removed { field.name.push_str(" (removed)") }
+        field.value = format!("<@{}> by <@{}>\n{}", case.member_id, case.moderator_id, field.value);
+        field
+    }).collect();
+
+    let embed = Embed {
+        author: None,
+        color: None,
+        description: None,
+        fields,
+        footer: Some(EmbedFooter {
+            icon_url: None,
+            proxy_icon_url: None,
+            text: format!("Found cases: {total} | Page {page}/{pages}")
+        }),
+        image: None,
+        kind: "".to_string(),
+        provider: None,
+        thumbnail: None,
+        timestamp: None,
+        title: Some("Case search".to_string()),
+        url: None,
+        video: None
+    };
+
+    // filters which don't fit into the custom id can be paginated only with the page option
+    let components = filters.to_custom_id().filter(|_| pages > 1).map(|custom_id| {
+        let options = (1..=pages.min(25)).map(|page| SelectMenuOption {
+            default: false,
+            description: None,
+            emoji: None,
+            label: format!("Page {page}"),
+            value: page.to_string()
+        }).collect();
+
+        vec![
+            Component::ActionRow(ActionRow {
+                components: vec![
+                    Component::SelectMenu(SelectMenu {
+                        custom_id,
+                        disabled: false,
+                        max_values: Some(1),
+                        min_values: Some(1),
+                        options,
+                        placeholder: None
+                    })
+                ]
+            })
+        ]
+    });
+
+    Ok((InteractionResponseData {
+        allowed_mentions: None,
+        attachments: None,
+        choices: None,
+        components,
+        content: None,
+        custom_id: None,
+        embeds: Some(vec![embed]),
+        flags: None,
+        title: None,
+        tts: None
+    }, None))
+}
+
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for char in value.chars() {
+        if "\\.+*?()|[]{}^$#-".contains(char) { escaped.push('\\') }
+        escaped.push(char);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::case::search::escape_regex;
+
+    #[test]
+    fn test_escape_regex() {
+        assert_eq!(escape_regex("spam (links)"), "spam \\(links\\)");
+        assert_eq!(escape_regex("a.b*"), "a\\.b\\*");
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use crate::context::Context;
+use crate::utils::embeds::EmbedBuilder;
+use crate::commands::context::InteractionContext;
+use crate::commands::ResponseData;
+use crate::{extract, get_option};
+use crate::models::config::GuildConfig;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    _: Arc<Client>,
+    config: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id, member);
+    extract!(member, user);
+
+    let target_id = get_option!(
+        interaction.options.get("member"), CommandOptionValue::User
+    ).copied().unwrap_or(user.id);
+
+    let (xp, position) = context.redis.get_by_user(
+        format!("levels.{guild_id}"), target_id
+    ).await.map_err(|_| "This member has no XP yet")?;
+
+    let curve = config.levels.curve;
+    let (level, level_xp) = curve.level_from_xp(xp as u64);
+    let required_xp = curve.xp_to_next_level(level);
+
+    Ok((
+        EmbedBuilder::new()
+            .title("Level".to_string())
+            .description(format!(
+                "<@{target_id}> is on level **{level}** and **#{}** on the server\n**{level_xp}/{required_xp}** XP to the next level (**{xp}** XP in total)",
+                position + 1
+            ))
+            .to_interaction_response_data(false),
+        None
+    ))
+}
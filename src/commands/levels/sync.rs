@@ -0,0 +1,76 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use twilight_http::Client;
+use twilight_http::error::ErrorType;
+use twilight_model::channel::message::MessageFlags;
+use twilight_model::http::interaction::InteractionResponseData;
+use twilight_model::id::Id;
+use twilight_model::id::marker::UserMarker;
+use crate::context::Context;
+use crate::models::config::GuildConfig;
+use crate::utils::errors::Error;
+use crate::commands::context::InteractionContext;
+use crate::commands::ResponseData;
+use crate::{extract, ok_or_skip};
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    discord_http: Arc<Client>,
+    config: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id);
+
+    if config.levels.rewards.is_empty() {
+        return Err(Error::from("There are no level rewards set"))
+    }
+
+    let leaderboard = context.redis.get_whole(format!("levels.{guild_id}")).await.map_err(Error::from)?;
+
+    let mut updated = 0;
+    // members which couldn't be fetched or updated are skipped, so one failure doesn't stop the sync
+    let mut failed = 0;
+
+    for (user_id, xp) in leaderboard {
+        let user_id = ok_or_skip!(Id::<UserMarker>::from_str(user_id.as_str()), Ok);
+        let (level, _) = config.levels.curve.level_from_xp(xp as u64);
+
+        let member = match discord_http.guild_member(guild_id, user_id).await {
+            Ok(member) => member.model().await,
+            Err(error) => {
+                // members which left the server aren't counted as failures
+                if !matches!(error.kind(), ErrorType::Response { status, .. } if status == &404) { failed += 1 }
+                continue
+            }
+        };
+        let Ok(member) = member else {
+            failed += 1;
+            continue
+        };
+
+        if let Some(roles) = config.levels.roles_for_level(level, &member.roles) {
+            match discord_http.update_guild_member(guild_id, user_id).roles(&roles).await {
+                Ok(_) => updated += 1,
+                Err(_) => failed += 1
+            }
+        }
+    }
+
+    let mut content = format!("**Updated roles of {updated} members**");
+    if failed > 0 {
+        content.push_str(&format!("\nCannot update {failed} members, check if the bot role is above the reward roles"));
+    }
+
+    Ok((InteractionResponseData {
+        allowed_mentions: None,
+        attachments: None,
+        choices: None,
+        components: None,
+        content: Some(content),
+        custom_id: None,
+        embeds: None,
+        flags: Some(MessageFlags::EPHEMERAL),
+        title: None,
+        tts: None
+    }, None))
+}
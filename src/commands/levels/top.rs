@@ -0,0 +1,46 @@
+use std::sync::Arc;
+use twilight_http::Client;
+use crate::context::Context;
+use crate::models::config::GuildConfig;
+use crate::utils::embeds::EmbedBuilder;
+use crate::utils::errors::Error;
+use crate::commands::context::InteractionContext;
+use crate::commands::ResponseData;
+use crate::extract;
+
+const LEADERBOARD_SIZE: isize = 10;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    _: Arc<Client>,
+    config: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id);
+
+    let leaderboard = context.redis.get_all(
+        format!("levels.{guild_id}"), LEADERBOARD_SIZE
+    ).await.map_err(Error::from)?;
+
+    if leaderboard.is_empty() {
+        return Err(Error::from("Nobody has gained any XP yet"))
+    }
+
+    let leaderboard_string = leaderboard
+        .iter()
+        .enumerate()
+        .map(|(index, (user_id, xp))| -> String {
+            let (level, _) = config.levels.curve.level_from_xp(*xp as u64);
+            format!("**{}.** <@{user_id}> level **{level}** ({xp} XP)", index + 1)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    Ok((
+        EmbedBuilder::new()
+            .title("Top users by level".to_string())
+            .description(leaderboard_string)
+            .to_interaction_response_data(false),
+        None
+    ))
+}
@@ -1,9 +1,11 @@
 pub mod top;
 pub mod case;
+pub mod levels;
 pub mod context;
 pub mod moderation;
 pub mod options;
 pub mod settings;
+pub mod tasks;
 
 use std::future::Future;
 use std::pin::Pin;
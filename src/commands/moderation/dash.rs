@@ -9,6 +9,7 @@ use crate::commands::context::InteractionContext;
 use crate::models::config::GuildConfig;
 use crate::utils::errors::Error;
 use crate::utils::modals::{ModalBuilder, RepetitiveTextInput};
+use crate::utils::uppercase::FirstLetterToUpperCase;
 
 pub async fn run(
     interaction: InteractionContext,
@@ -38,6 +39,10 @@ pub async fn run(
             .add_repetitive_component(RepetitiveTextInput::Member)
             .add_repetitive_component(RepetitiveTextInput::Duration(false))
             .add_repetitive_component(RepetitiveTextInput::Reason)
+    } else if ["unban", "unmute", "untimeout"].contains(&action.as_str()) {
+        ModalBuilder::new(format!("a:{action}-d"), action.to_owned().first_to_uppercase())
+            .add_repetitive_component(RepetitiveTextInput::Member)
+            .add_repetitive_component(RepetitiveTextInput::Reason)
     } else { return Err(Error::from("Unknown action")) };
 
     Ok((
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+use chrono::Utc;
+use mongodb::bson::DateTime;
+use twilight_http::Client;
+use twilight_model::id::Id;
+use twilight_model::id::marker::UserMarker;
+use twilight_model::util::Timestamp;
+use crate::context::Context;
+use crate::models::case::{Case, CaseActionType};
+use crate::models::config::GuildConfig;
+use crate::models::config::moderation::EscalationAction;
+use crate::models::task::{Task, TaskAction};
+use crate::utils::errors::Error;
+
+/// Checks escalation rules after the member got a warn and applies the reached one as a new case
+/// which refers to the counted warns
+pub async fn run(
+    context: Arc<Context>,
+    discord_http: Arc<Client>,
+    config: &GuildConfig,
+    member_id: Id<UserMarker>
+) -> Result<(), Error> {
+    if config.moderation.escalations.is_empty() { return Ok(()) }
+
+    let guild_id = config.guild_id;
+    let now = DateTime::now().timestamp_millis();
+
+    let warns = context.mongodb.get_active_member_cases(guild_id, member_id, CaseActionType::Warn)
+        .await?
+        .iter()
+        .map(|case| (case.index, case.created_at.timestamp_millis(), case.points))
+        .collect::<Vec<(u64, i64, u32)>>();
+
+    let (escalation, related_cases) = match config.moderation.get_escalation(&warns, now) {
+        Some(escalation) => escalation,
+        None => return Ok(())
+    };
+
+    let (action, duration) = match escalation.action {
+        EscalationAction::Timeout { duration } => {
+            let timestamp = Timestamp::from_secs(Utc::now().timestamp() + duration as i64)
+                .map_err(Error::from)?;
+            discord_http.update_guild_member(guild_id, member_id)
+                .communication_disabled_until(Some(timestamp)).map_err(Error::from)?
+                .await.map_err(Error::from)?;
+            (CaseActionType::Timeout, Some(duration))
+        },
+        EscalationAction::Mute { duration } => {
+            let mute_role = config.moderation.mute_role.ok_or("There is no role for muted users set")?;
+            discord_http.add_guild_member_role(guild_id, member_id, mute_role)
+                .await.map_err(Error::from)?;
+            context.mongodb.create_task(&context.redis, Task::new(
+                guild_id,
+                DateTime::from_millis(now + (duration as i64) * 1000),
+                TaskAction::RemoveMuteRole(member_id)
+            )).await?;
+            (CaseActionType::Mute, Some(duration))
+        },
+        EscalationAction::Kick => {
+            discord_http.remove_guild_member(guild_id, member_id).await.map_err(Error::from)?;
+            (CaseActionType::Kick, None)
+        },
+        EscalationAction::Ban { duration } => {
+            discord_http.create_ban(guild_id, member_id).await.map_err(Error::from)?;
+            // the unban is scheduled only after the ban succeeded
+            if let Some(duration) = duration {
+                context.mongodb.create_task(&context.redis, Task::new(
+                    guild_id,
+                    DateTime::from_millis(now + (duration as i64) * 1000),
+                    TaskAction::RemoveBan(member_id)
+                )).await?;
+            }
+            (CaseActionType::Ban, duration)
+        }
+    };
+
+    let moderator_id = discord_http.current_user()
+        .await.map_err(Error::from)?
+        .model().await.map_err(Error::from)?.id;
+
+    let index = context.mongodb.get_next_case_index(guild_id).await?;
+
+    let created_at = DateTime::now();
+    let (expires_at, points) = config.moderation.get_case_expiry(&action, created_at);
+
+    let case = Case {
+        moderator_id,
+        created_at,
+        guild_id,
+        member_id,
+        action,
+        reason: Some(format!("Reached {} warn points", escalation.warns)),
+        removed: false,
+        duration: duration.map(|duration| duration as i64),
+        index,
+        related_cases,
+        expires_at,
+        points,
+        history: vec![]
+    };
+
+    let embed = case.to_embed(discord_http.to_owned()).await?;
+
+    context.mongodb.create_case(
+        discord_http,
+        &context.redis,
+        case,
+        embed,
+        if config.moderation.dm_case { Some(member_id) } else { None },
+        config.moderation.logs_channel
+    ).await
+}
@@ -16,6 +16,7 @@ use crate::commands::ResponseData;
 use crate::context::Context;
 use crate::{extract, get_option, get_required_option};
 use crate::commands::context::{InteractionContext, InteractionHelpers};
+use crate::commands::moderation::escalation;
 use crate::database::redis::RedisConnection;
 use crate::models::case::{Case, CaseActionType};
 use crate::models::config::GuildConfig;
@@ -106,26 +107,33 @@ pub async fn run(
             discord_http.update_guild_member(config.guild_id, target_id)
                 .roles(&roles).await.map_err(Error::from)?;
 
-            context.mongodb.create_task(Task {
-                execute_at: DateTime::from_millis(end_at * 1000),
+            context.mongodb.create_task(&context.redis, Task::new(
                 guild_id,
-                action: TaskAction::RemoveMuteRole(target_id)
-            }).await?;
+                DateTime::from_millis(end_at * 1000),
+                TaskAction::RemoveMuteRole(target_id)
+            )).await?;
         }
     };
 
-    let index = context.mongodb.get_next_case_index(guild_id).await? as u16;
+    let index = context.mongodb.get_next_case_index(guild_id).await?;
+
+    let created_at = DateTime::now();
+    let (expires_at, points) = config.moderation.get_case_expiry(&case_type, created_at);
 
     let case = Case {
         moderator_id: user_id,
-        created_at: DateTime::now(),
+        created_at,
         guild_id,
         member_id: target_id,
         action: case_type,
         reason,
         removed: false,
         duration: duration.map(|(d, _)| d.as_secs() as i64),
-        index
+        index,
+        related_cases: vec![],
+        expires_at,
+        points,
+        history: vec![]
     };
 
     let result_action = match interaction.command_text.as_str() {
@@ -134,11 +142,11 @@ pub async fn run(
         },
         "ban" => {
             if let Some((_, end_at)) = duration {
-                context.mongodb.create_task(Task {
-                    execute_at: DateTime::from_millis(end_at * 1000),
+                context.mongodb.create_task(&context.redis, Task::new(
                     guild_id,
-                    action: TaskAction::RemoveBan(target_id)
-                }).await?;
+                    DateTime::from_millis(end_at * 1000),
+                    TaskAction::RemoveBan(target_id)
+                )).await?;
             };
             discord_http.create_ban(guild_id, target_id).await.err()
         },
@@ -154,13 +162,19 @@ pub async fn run(
         config.moderation.logs_channel
     ).await.err();
 
+    let result_escalation = if interaction.command_text == "warn" {
+        escalation::run(context.to_owned(), discord_http.to_owned(), &config, target_id).await.err()
+    } else { None };
+
     Ok((InteractionResponseData {
         allowed_mentions: None,
         attachments: None,
         choices: None,
         components: None,
-        content: if result_action.is_some() || result_case.is_some() {
-            Some(format!("Action status: {result_action:?}\nCase status: {result_case:?}"))
+        content: if result_action.is_some() || result_case.is_some() || result_escalation.is_some() {
+            Some(format!(
+                "Action status: {result_action:?}\nCase status: {result_case:?}\nEscalation status: {result_escalation:?}"
+            ))
         } else { None },
         custom_id: None,
         embeds: Some(vec![case_embed]),
@@ -197,7 +211,7 @@ fn command_to_action_type(command_name: &str, config: &GuildConfig) -> Option<Ca
     Some(action_type)
 }
 
-fn create_modal(command_name: String, target_id: Id<GenericMarker>) -> ModalBuilder {
+pub fn create_modal(command_name: String, target_id: Id<GenericMarker>) -> ModalBuilder {
     let modal = ModalBuilder::new(
         format!("a:{}:{target_id}", command_name),
         command_name.to_owned().first_to_uppercase()
@@ -213,7 +227,7 @@ fn create_modal(command_name: String, target_id: Id<GenericMarker>) -> ModalBuil
 }
 
 /// Fetch the guild member, but when the response status is 404 it return `Result::Ok(Option::None)`
-async fn get_target_member(
+pub async fn get_target_member(
     discord_http: &Arc<Client>,
     guild_id: Id<GuildMarker>,
     member_id: Id<UserMarker>
@@ -236,7 +250,7 @@ async fn get_target_member(
 }
 
 /// Get the highest role from array by checking positions in the sorted array of guild roles
-fn get_highest_role_pos(
+pub fn get_highest_role_pos(
     sorted_roles: &[Id<RoleMarker>],
     target_roles: &[Id<RoleMarker>]
 ) -> usize {
@@ -251,7 +265,7 @@ fn get_highest_role_pos(
 }
 
 /// Checks is position of the moderator role higher then position of the target role
-async fn check_position(
+pub async fn check_position(
     redis: &RedisConnection,
     guild_id: Id<GuildMarker>,
     target_member: &Member,
@@ -1,3 +1,6 @@
 pub mod dash;
 pub mod clear;
-pub mod execute;
\ No newline at end of file
+pub mod escalation;
+pub mod execute;
+pub mod revert;
+pub mod temprole;
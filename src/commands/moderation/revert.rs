@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use mongodb::bson::DateTime;
+use twilight_http::Client;
+use twilight_http::error::ErrorType;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::channel::message::MessageFlags;
+use twilight_model::http::interaction::{InteractionResponseData, InteractionResponseType};
+use crate::commands::ResponseData;
+use crate::context::Context;
+use crate::{extract, get_option, get_required_option};
+use crate::commands::context::{InteractionContext, InteractionHelpers};
+use crate::commands::moderation::execute::{check_position, create_modal, get_target_member};
+use crate::models::case::{Case, CaseActionType};
+use crate::models::config::GuildConfig;
+use crate::models::config::moderation::MuteMode;
+use crate::models::task::TaskAction;
+use crate::utils::errors::Error;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    discord_http: Arc<Client>,
+    config: GuildConfig
+) -> ResponseData {
+    if let Some(target_user) = interaction.orginal.target_id() {
+        let response = create_modal(
+            interaction.command_text, target_user
+        ).to_interaction_response_data();
+        return Ok((response, Some(InteractionResponseType::Modal)))
+    }
+
+    extract!(interaction.orginal, guild_id, member);
+    extract!(&member, user);
+
+    let user_id = user.id;
+
+    let target_id = *get_required_option!(
+        interaction.options.get("member"), CommandOptionValue::User
+    );
+
+    let reason = get_option!(
+        interaction.options.get("reason"), CommandOptionValue::String
+    ).cloned();
+
+    let (case_type, reverted_type) = command_to_action_types(
+        interaction.command_text.as_str(), &config
+    ).ok_or("Cannot find any action type matching command name")?;
+
+    let target_member = get_target_member(
+        &discord_http, guild_id, target_id
+    ).await?;
+
+    if let Some(target_member) = &target_member {
+        if !check_position(&context.redis, guild_id, target_member, member).await? {
+            return Err(
+                Error::from("Missing Permissions: Cannot execute moderation action on user with higher role")
+            )
+        }
+    }
+
+    match case_type {
+        CaseActionType::Unban => {
+            discord_http.delete_ban(guild_id, target_id).await.map_err(|error| match error.kind() {
+                ErrorType::Response { status, .. } if status == &404 => Error::from("This user isn't banned"),
+                _ => Error::from(error)
+            })?;
+            context.mongodb.delete_tasks(guild_id, TaskAction::RemoveBan(target_id)).await?;
+        },
+        CaseActionType::Untimeout => {
+            let timed_out_until = target_member
+                .ok_or("You can remove timeouts only from server members (User left or didn't join this server)")?
+                .communication_disabled_until;
+            let is_timed_out = timed_out_until
+                .map(|until| until.as_secs() > chrono::Utc::now().timestamp())
+                .unwrap_or(false);
+            if !is_timed_out { return Err(Error::from("This member isn't timed out")) }
+
+            discord_http
+                .update_guild_member(guild_id, target_id)
+                .communication_disabled_until(None)
+                .map_err(Error::from)?
+                .await.map_err(Error::from)?;
+        },
+        CaseActionType::Unmute => {
+            let mute_role = config.moderation.mute_role.ok_or("There is no role for muted users set")?;
+            let mut roles = target_member
+                .ok_or("You can unmute only server members (User left or didn't join this server)")?
+                .roles;
+            if !roles.contains(&mute_role) { return Err(Error::from("This member isn't muted")) }
+            roles.retain(|role| role != &mute_role);
+
+            discord_http.update_guild_member(guild_id, target_id)
+                .roles(&roles).await.map_err(Error::from)?;
+            context.mongodb.delete_tasks(guild_id, TaskAction::RemoveMuteRole(target_id)).await?;
+        },
+        _ => return Err(Error::from("Unsupported action type"))
+    };
+
+    let related_cases = context.mongodb.get_last_case(guild_id, target_id, reverted_type)
+        .await?
+        .map(|case| vec![case.index])
+        .unwrap_or_default();
+
+    let index = context.mongodb.get_next_case_index(guild_id).await?;
+
+    let created_at = DateTime::now();
+    let (expires_at, points) = config.moderation.get_case_expiry(&case_type, created_at);
+
+    let case = Case {
+        moderator_id: user_id,
+        created_at,
+        guild_id,
+        member_id: target_id,
+        action: case_type,
+        reason,
+        removed: false,
+        duration: None,
+        index,
+        related_cases,
+        expires_at,
+        points,
+        history: vec![]
+    };
+
+    let case_embed = case.to_embed(discord_http.to_owned()).await?;
+
+    let result_case = context.mongodb.create_case(
+        discord_http.to_owned(), &context.redis, case,
+        case_embed.to_owned(),
+        if config.moderation.dm_case { Some(target_id) } else { None },
+        config.moderation.logs_channel
+    ).await.err();
+
+    Ok((InteractionResponseData {
+        allowed_mentions: None,
+        attachments: None,
+        choices: None,
+        components: None,
+        content: result_case.map(|result_case| format!("Case status: {result_case:?}")),
+        custom_id: None,
+        embeds: Some(vec![case_embed]),
+        flags: Some(MessageFlags::EPHEMERAL),
+        title: None,
+        tts: None
+    }, None))
+}
+
+/// Returns the action type of the created case and the action type of the lifted punishment
+fn command_to_action_types(command_name: &str, config: &GuildConfig) -> Option<(CaseActionType, CaseActionType)> {
+    let action_types = match command_name {
+        "unban" => (CaseActionType::Unban, CaseActionType::Ban),
+        "untimeout" | "unmute" => {
+            let is_timeout = match config.moderation.mute_mode {
+                MuteMode::Timeout => true,
+                MuteMode::Role => false,
+                MuteMode::DependOnCommand => command_name == "untimeout"
+            };
+
+            if is_timeout { (CaseActionType::Untimeout, CaseActionType::Timeout) }
+            else { (CaseActionType::Unmute, CaseActionType::Mute) }
+        },
+        _ => return None
+    };
+
+    Some(action_types)
+}
@@ -0,0 +1,68 @@
+use std::sync::Arc;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use crate::commands::ResponseData;
+use crate::commands::context::InteractionContext;
+use crate::commands::moderation::execute::{check_position, get_highest_role_pos, get_target_member};
+use crate::commands::tasks::parse_execute_at;
+use crate::context::Context;
+use crate::{extract, get_option, get_required_option};
+use crate::models::config::GuildConfig;
+use crate::models::task::{Task, TaskAction};
+use crate::utils::embeds::EmbedBuilder;
+use crate::utils::errors::Error;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    discord_http: Arc<Client>,
+    _: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id, member);
+
+    let member_id = *get_required_option!(
+        interaction.options.get("member"), CommandOptionValue::User
+    );
+    let role_id = *get_required_option!(
+        interaction.options.get("role"), CommandOptionValue::Role
+    );
+    let duration = get_required_option!(
+        interaction.options.get("duration"), CommandOptionValue::String
+    );
+
+    let execute_at = parse_execute_at(duration.as_str())?;
+
+    let guild = context.redis.get_guild(guild_id).await?;
+    if get_highest_role_pos(&guild.roles, &[role_id]) >= get_highest_role_pos(&guild.roles, &member.roles) {
+        return Err(Error::from("Missing Permissions: Cannot give a role which is higher than your highest role"))
+    }
+
+    let target_member = get_target_member(&discord_http, guild_id, member_id)
+        .await?
+        .ok_or("You can give roles only to server members (User left or didn't join this server)")?;
+
+    if !check_position(&context.redis, guild_id, &target_member, member).await? {
+        return Err(
+            Error::from("Missing Permissions: Cannot execute moderation action on user with higher role")
+        )
+    }
+
+    discord_http.add_guild_member_role(guild_id, member_id, role_id)
+        .await.map_err(Error::from)?;
+
+    context.mongodb.create_task(&context.redis, Task::new(
+        guild_id,
+        execute_at,
+        TaskAction::RemoveRole { member_id, role_id }
+    )).await?;
+
+    Ok((
+        EmbedBuilder::new()
+            .title("Temporary role".to_string())
+            .description(format!(
+                "<@{member_id}> got <@&{role_id}> until <t:{}:f>", execute_at.timestamp_millis() / 1000
+            ))
+            .to_interaction_response_data(true),
+        None
+    ))
+}
@@ -105,6 +105,8 @@ fn set_options_from_custom_id(
     for i in 2..custom_id.len() {
         let value = ok_or_break!(custom_id.get(i), Some);
         let (name, kind) = ok_or_break!(component.get(i - 2), Some);
+        // empty parts are options which weren't set
+        if value.is_empty() { continue }
         options.insert(name, convert_value_to_option(value.as_str(), &kind)?);
     }
     Ok(())
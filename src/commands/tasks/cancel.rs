@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use mongodb::bson::oid::ObjectId;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use crate::commands::ResponseData;
+use crate::commands::context::InteractionContext;
+use crate::context::Context;
+use crate::{extract, get_option, get_required_option};
+use crate::models::config::GuildConfig;
+use crate::utils::embeds::EmbedBuilder;
+use crate::utils::errors::Error;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    _: Arc<Client>,
+    _: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id);
+
+    let task_id = get_required_option!(
+        interaction.options.get("id"), CommandOptionValue::String
+    );
+    let task_id = ObjectId::parse_str(task_id).map_err(|_| "Invalid task ID")?;
+
+    if !context.mongodb.delete_guild_task(guild_id, task_id).await? {
+        return Err(Error::from("Cannot find a pending task with this ID"))
+    }
+
+    Ok((
+        EmbedBuilder::new()
+            .title("Task cancelled".to_string())
+            .description(format!("Task `{}` won't be executed", task_id.to_hex()))
+            .to_interaction_response_data(true),
+        None
+    ))
+}
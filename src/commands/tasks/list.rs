@@ -0,0 +1,43 @@
+use std::sync::Arc;
+use twilight_http::Client;
+use crate::commands::ResponseData;
+use crate::commands::context::InteractionContext;
+use crate::context::Context;
+use crate::extract;
+use crate::models::config::GuildConfig;
+use crate::utils::embeds::EmbedBuilder;
+
+const TASKS_LIMIT: i64 = 20;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    _: Arc<Client>,
+    _: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id);
+
+    let tasks = context.mongodb.get_guild_tasks(guild_id, TASKS_LIMIT).await?;
+
+    let description = if tasks.is_empty() {
+        "There are no pending tasks".to_string()
+    } else {
+        tasks.iter()
+            .map(|task| format!(
+                "`{}` <t:{}:R> {}",
+                task.id.map(|id| id.to_hex()).unwrap_or_default(),
+                task.execute_at.timestamp_millis() / 1000,
+                task.action.describe()
+            ))
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+
+    Ok((
+        EmbedBuilder::new()
+            .title("Pending tasks".to_string())
+            .description(description)
+            .to_interaction_response_data(true),
+        None
+    ))
+}
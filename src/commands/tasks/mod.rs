@@ -0,0 +1,21 @@
+pub mod cancel;
+pub mod list;
+pub mod remind;
+pub mod schedule;
+
+use std::str::FromStr;
+use humantime::Duration;
+use mongodb::bson::DateTime;
+use crate::utils::errors::Error;
+
+/// Returns the time after the humantime duration passes from now
+pub fn parse_execute_at(duration: &str) -> Result<DateTime, Error> {
+    let duration = Duration::from_str(duration)
+        .map_err(|_| "Invalid duration string (try 3m, 10s, 2d)")?;
+
+    let execute_at = i64::try_from(duration.as_millis()).ok()
+        .and_then(|duration| DateTime::now().timestamp_millis().checked_add(duration))
+        .ok_or("Duration is too long")?;
+
+    Ok(DateTime::from_millis(execute_at))
+}
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use crate::commands::ResponseData;
+use crate::commands::context::InteractionContext;
+use crate::commands::tasks::parse_execute_at;
+use crate::context::Context;
+use crate::{extract, get_option, get_required_option};
+use crate::models::config::GuildConfig;
+use crate::models::task::{Task, TaskAction};
+use crate::utils::embeds::EmbedBuilder;
+use crate::utils::errors::Error;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    _: Arc<Client>,
+    _: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id, channel_id, member);
+    extract!(member, user);
+
+    let duration = get_required_option!(
+        interaction.options.get("duration"), CommandOptionValue::String
+    );
+    let content = get_required_option!(
+        interaction.options.get("content"), CommandOptionValue::String
+    );
+
+    let execute_at = parse_execute_at(duration.as_str())?;
+    // the reminder is sent with the mention before the content
+    twilight_validate::message::content(format!("<@{}> {content}", user.id)).map_err(Error::from)?;
+
+    context.mongodb.create_task(&context.redis, Task::new(guild_id, execute_at, TaskAction::Remind {
+        member_id: user.id,
+        channel_id,
+        content: content.to_owned()
+    })).await?;
+
+    Ok((
+        EmbedBuilder::new()
+            .title("Reminder set".to_string())
+            .description(format!("I will remind you <t:{}:R>", execute_at.timestamp_millis() / 1000))
+            .to_interaction_response_data(true),
+        None
+    ))
+}
@@ -0,0 +1,80 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::channel::message::{AllowedMentions, MentionType};
+use twilight_model::guild::Permissions;
+use twilight_model::id::Id;
+use crate::commands::ResponseData;
+use crate::commands::context::{InteractionContext, InteractionHelpers};
+use crate::commands::tasks::parse_execute_at;
+use crate::context::Context;
+use crate::{extract, get_option, get_required_option};
+use crate::models::config::GuildConfig;
+use crate::models::task::{Task, TaskAction};
+use crate::utils::embeds::EmbedBuilder;
+use crate::utils::errors::Error;
+
+pub async fn run(
+    interaction: InteractionContext,
+    context: Arc<Context>,
+    _: Arc<Client>,
+    _: GuildConfig
+) -> ResponseData {
+    extract!(interaction.orginal, guild_id, channel_id);
+
+    let duration = get_required_option!(
+        interaction.options.get("duration"), CommandOptionValue::String
+    );
+    let execute_at = parse_execute_at(duration.as_str())?;
+
+    let action = match interaction.command_text.as_str() {
+        "schedule message" => {
+            let target_channel_id = *get_required_option!(
+                interaction.options.get("channel"), CommandOptionValue::Channel
+            );
+            let content = get_required_option!(
+                interaction.options.get("content"), CommandOptionValue::String
+            );
+            twilight_validate::message::content(content).map_err(Error::from)?;
+
+            let permissions = interaction.orginal.resolved()
+                .and_then(|resolved| resolved.channels.get(&target_channel_id))
+                .map(|channel| channel.permissions)
+                .ok_or("Cannot find the selected channel")?;
+            if !permissions.contains(Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES) {
+                return Err(Error::from("Missing Permissions: You cannot send messages in the selected channel"))
+            }
+
+            // the message can ping only when the member could ping everyone in the channel
+            let allowed_mentions = if permissions.contains(Permissions::MENTION_EVERYONE) {
+                AllowedMentions {
+                    parse: vec![MentionType::Everyone, MentionType::Roles, MentionType::Users],
+                    ..Default::default()
+                }
+            } else { AllowedMentions::default() };
+
+            TaskAction::SendMessage { channel_id: target_channel_id, content: content.to_owned(), allowed_mentions }
+        },
+        "schedule delete" => {
+            let message_id = get_required_option!(
+                interaction.options.get("message"), CommandOptionValue::String
+            );
+            let message_id = Id::from_str(message_id.as_str()).map_err(|_| "Invalid message ID")?;
+
+            TaskAction::DeleteMessage { channel_id, message_id }
+        },
+        _ => return Err(Error::from("Unknown schedule command"))
+    };
+
+    let description = action.describe();
+    context.mongodb.create_task(&context.redis, Task::new(guild_id, execute_at, action)).await?;
+
+    Ok((
+        EmbedBuilder::new()
+            .title("Task scheduled".to_string())
+            .description(format!("{description} <t:{}:R>", execute_at.timestamp_millis() / 1000))
+            .to_interaction_response_data(true),
+        None
+    ))
+}
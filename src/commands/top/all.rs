@@ -1,14 +1,22 @@
 use std::sync::Arc;
+use mongodb::bson::doc;
+use mongodb::options::FindOneOptions;
 use twilight_http::Client;
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use twilight_model::channel::message::Component;
+use twilight_model::channel::message::component::{ActionRow, SelectMenu, SelectMenuOption};
+use twilight_model::http::interaction::InteractionResponseData;
 use crate::context::Context;
 use crate::models::config::GuildConfig;
 use crate::utils::embeds::EmbedBuilder;
 use crate::utils::errors::Error;
 use crate::commands::context::InteractionContext;
 use crate::commands::ResponseData;
-use crate::extract;
+use crate::commands::top::{get_period, period_name};
+use crate::{extract, get_option};
 
 const PLACES_EMOTES: [&str; 3] = [":first_place:", ":second_place:", ":third_place:"];
+const PAGE_SIZE: usize = 10;
 
 pub async fn run(
     interaction: InteractionContext,
@@ -18,30 +26,109 @@ pub async fn run(
 ) -> ResponseData {
     extract!(interaction.orginal, guild_id);
 
-    let week_or_day = interaction.command_vec.get(1).cloned()
-        .ok_or("Invalid command")?;
-    if !["week", "day"].contains(&week_or_day.as_str()) {
-        return Err(Error::from("Invalid command"))
+    let period = get_period(&interaction)?;
+
+    // 0 is the current period, 1 the last finished one and so on
+    let ago = u64::try_from(
+        get_option!(
+            interaction.options.get("ago"), CommandOptionValue::Integer
+        ).copied().unwrap_or(0)
+    ).map_err(|_| "Number of periods ago must be u64")?;
+
+    let page = u64::try_from(
+        get_option!(
+            interaction.options.get("page"), CommandOptionValue::Integer
+        ).copied().unwrap_or(1)
+    ).map_err(|_| "Page must be u64")?.max(1);
+
+    let offset = (page as usize - 1) * PAGE_SIZE;
+
+    let (leaderboard, total, ended_at) = if ago == 0 {
+        let path = format!("top_{period}.{guild_id}");
+        let leaderboard = context.redis.get_range(
+            path.to_owned(), offset as isize, PAGE_SIZE as isize
+        ).await.map_err(Error::from)?;
+        let total = context.redis.count(path).await.map_err(Error::from)?;
+        (leaderboard, total, None)
+    } else {
+        let archive = context.mongodb.top_archives.find_one(
+            doc! { "guild_id": guild_id.to_string(), "period": period.as_str() },
+            FindOneOptions::builder()
+                .sort(doc! { "ended_at": -1_i32 })
+                .skip(Some(ago - 1)).build()
+        ).await.map_err(Error::from)?.ok_or("There is no archived ranking for the selected period")?;
+
+        let total = archive.ranking.len();
+        let leaderboard = archive.ranking.into_iter()
+            .skip(offset).take(PAGE_SIZE)
+            .map(|entry| (entry.user_id.to_string(), entry.messages))
+            .collect::<Vec<(String, u32)>>();
+        (leaderboard, total, Some(archive.ended_at))
+    };
+
+    if leaderboard.is_empty() {
+        return Err(Error::from("There is no one on this page of the leaderboard"))
     }
 
-    let leaderboard = context.redis.get_all(
-        format!("top_{week_or_day}.{guild_id}"), 3
-    ).await.map_err(Error::from)?;
+    let mut description = leaderboard_to_string(&leaderboard, offset);
+    if let Some(ended_at) = ended_at {
+        description.insert_str(0, &format!("Ended <t:{}:R>\n\n", ended_at.timestamp_millis() / 1000));
+    }
+
+    let embed = EmbedBuilder::new()
+        .title(format!("Top users of {}", period_name(&period)))
+        .description(description)
+        .to_embed();
+
+    let pages = if total % PAGE_SIZE == 0 { total / PAGE_SIZE } else { total / PAGE_SIZE + 1 };
+
+    let components = (pages > 1).then(|| vec![
+        Component::ActionRow(ActionRow {
+            components: vec![
+                Component::SelectMenu(SelectMenu {
+                    custom_id: format!("a:top:{period}:{ago}"),
+                    disabled: false,
+                    max_values: Some(1),
+                    min_values: Some(1),
+                    options: (1..=pages.min(25)).map(|page| SelectMenuOption {
+                        default: false,
+                        description: None,
+                        emoji: None,
+                        label: format!("Page {page}"),
+                        value: page.to_string()
+                    }).collect(),
+                    placeholder: None
+                })
+            ]
+        })
+    ]);
+
+    Ok((InteractionResponseData {
+        allowed_mentions: None,
+        attachments: None,
+        choices: None,
+        components,
+        content: None,
+        custom_id: None,
+        embeds: Some(vec![embed]),
+        flags: None,
+        title: None,
+        tts: None
+    }, None))
+}
 
-    let leaderboard_string = leaderboard
+/// Formats the leaderboard, `offset` is the position of the first entry
+pub fn leaderboard_to_string(leaderboard: &[(String, u32)], offset: usize) -> String {
+    leaderboard
         .iter()
         .enumerate()
         .map(|(index, (user_id, messages))| -> String {
-            format!("{} > <@{user_id}> ({messages})", PLACES_EMOTES[index])
+            let position = offset + index;
+            let place = PLACES_EMOTES.get(position)
+                .map(|emote| emote.to_string())
+                .unwrap_or_else(|| format!("**#{}**", position + 1));
+            format!("{place} > <@{user_id}> ({messages})")
         })
         .collect::<Vec<String>>()
-        .join("\n");
-
-    Ok((
-        EmbedBuilder::new()
-            .title(format!("Top {week_or_day} users"))
-            .description(leaderboard_string)
-            .to_interaction_response_data(false),
-        None
-    ))
-}
\ No newline at end of file
+        .join("\n")
+}
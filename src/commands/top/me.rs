@@ -5,6 +5,7 @@ use crate::utils::embeds::EmbedBuilder;
 use crate::utils::errors::Error;
 use crate::commands::context::InteractionContext;
 use crate::commands::ResponseData;
+use crate::commands::top::{get_period, period_name};
 use crate::extract;
 use crate::models::config::GuildConfig;
 
@@ -14,15 +15,11 @@ pub async fn run(
     _: Arc<Client>,
     _: GuildConfig
 ) -> ResponseData {
+    let week_or_day = get_period(&interaction)?;
+
     extract!(interaction.orginal, guild_id, member);
     extract!(member, user);
 
-    let week_or_day = interaction.command_vec.get(1).cloned()
-        .ok_or("Invalid command")?;
-    if !["week", "day"].contains(&week_or_day.as_str()) {
-        return Err(Error::from("Invalid command"))
-    }
-
     let (user_score, user_position) = context.redis.get_by_user(
         format!("top_{week_or_day}.{guild_id}"), user.id
     ).await.map_err(Error::from)?;
@@ -58,7 +55,7 @@ pub async fn run(
     Ok((
         EmbedBuilder::new()
             .title(
-                format!("Top of the {week_or_day} for {}#{}", user.name, user.discriminator)
+                format!("Top of {} for {}#{}", period_name(&week_or_day), user.name, user.discriminator)
             )
             .description(result)
             .to_interaction_response_data(false),
@@ -1,2 +1,32 @@
 pub mod all;
-pub mod me;
\ No newline at end of file
+pub mod me;
+
+use twilight_model::application::interaction::application_command::CommandOptionValue;
+use crate::commands::context::InteractionContext;
+use crate::get_option;
+use crate::utils::errors::Error;
+
+pub const PERIODS: [&str; 4] = ["day", "week", "month", "alltime"];
+
+/// Reads the period from the `period` option (set by components) or from the subcommand group name
+pub fn get_period(interaction: &InteractionContext) -> Result<String, Error> {
+    let period = get_option!(interaction.options.get("period"), CommandOptionValue::String)
+        .or_else(|| interaction.command_vec.get(1))
+        .cloned()
+        .ok_or("Invalid command")?;
+
+    if !PERIODS.contains(&period.as_str()) {
+        return Err(Error::from("Invalid command"))
+    }
+
+    Ok(period)
+}
+
+pub fn period_name(period: &str) -> &str {
+    match period {
+        "day" => "the day",
+        "week" => "the week",
+        "month" => "the month",
+        _ => "all time"
+    }
+}
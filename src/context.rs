@@ -4,11 +4,22 @@ use crate::{
     application::Application,
     database::{mongodb::MongoDBConnection, redis::RedisConnection},
 };
+use std::time::Duration;
+
+/// Times the case index migration is tried at startup
+const MIGRATION_ATTEMPTS: u32 = 3;
 
 all_macro!(
     cfg(feature = "gateway");
+    use std::sync::Arc;
     use crate::bucket::Bucket;
+    use crate::bucket::MemoryBucket;
+    use crate::bucket::RedisBucket;
+    use crate::duplicates::DuplicateStore;
     use crate::links::ScamLinks;
+    use crate::links::ScamLinksConfig;
+    use crate::resolver::HttpResolver;
+    use crate::resolver::Resolver;
 );
 
 pub struct Context {
@@ -19,6 +30,10 @@ pub struct Context {
     pub scam_domains: ScamLinks,
     #[cfg(feature = "gateway")]
     pub bucket: Bucket,
+    #[cfg(feature = "gateway")]
+    pub duplicates: DuplicateStore,
+    #[cfg(feature = "gateway")]
+    pub link_resolver: Resolver,
 }
 
 impl Context {
@@ -27,17 +42,32 @@ impl Context {
         let redis_url = env_unwrap!("REDIS_URL");
 
         let mongodb = MongoDBConnection::connect(mongodb_uri).await.unwrap();
+        // transient errors are retried, the bot starts also when the migration keeps failing
+        for attempt in 1..=MIGRATION_ATTEMPTS {
+            match mongodb.migrate_case_indexes().await {
+                Ok(()) => break,
+                Err(error) => {
+                    eprintln!("Cannot migrate case indexes (attempt {attempt}/{MIGRATION_ATTEMPTS}): {error:?}");
+                    if attempt < MIGRATION_ATTEMPTS {
+                        tokio::time::sleep(Duration::from_secs(2_u64.pow(attempt))).await;
+                    }
+                }
+            }
+        }
         let redis = RedisConnection::connect(redis_url).unwrap();
+        mongodb.listen_for_config_changes(redis.to_owned());
 
         #[cfg(feature = "gateway")]
-        let scam_domains = ScamLinks::new()
-            .await
-            .expect("Cannot load scam links manager");
+        let scam_domains = ScamLinks::new(ScamLinksConfig::from_env(), vec![]).await;
         #[cfg(feature = "gateway")]
         scam_domains.connect();
 
+        // BUCKET_STORAGE=memory keeps automod buckets in the process memory instead of Redis
         #[cfg(feature = "gateway")]
-        let bucket: Bucket = Default::default();
+        let bucket: Bucket = match std::env::var("BUCKET_STORAGE").as_deref() {
+            Ok("memory") => Arc::new(MemoryBucket::default()),
+            _ => Arc::new(RedisBucket::new(redis.to_owned()))
+        };
 
         let application = Application::new();
 
@@ -48,6 +78,10 @@ impl Context {
             scam_domains,
             #[cfg(feature = "gateway")]
             bucket,
+            #[cfg(feature = "gateway")]
+            duplicates: DuplicateStore::default(),
+            #[cfg(feature = "gateway")]
+            link_resolver: Arc::new(HttpResolver::new()),
             application,
         }
     }
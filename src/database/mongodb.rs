@@ -1,30 +1,68 @@
+use std::str::FromStr;
 use std::sync::Arc;
 use dashmap::DashMap;
-use futures_util::TryStreamExt;
-use mongodb::{Client, Collection, Database};
-use mongodb::bson::doc;
-#[cfg(feature = "tasks")]
-use mongodb::bson::DateTime;
+use futures_util::{StreamExt, TryStreamExt};
+use mongodb::{Client, Collection, Cursor, Database, IndexModel};
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::bson::oid::ObjectId;
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::{FindOneAndUpdateOptions, FindOneOptions, FindOptions, IndexOptions, ReplaceOptions, ReturnDocument, UpdateOptions};
+use serde::Deserialize;
 use twilight_model::channel::message::Embed;
 use twilight_model::id::Id;
 use twilight_model::id::marker::{ChannelMarker, GuildMarker, UserMarker};
 #[cfg(any(feature = "tasks", feature = "custom-clients"))]
 use crate::gateway::clients::ClientData;
-use crate::models::case::Case;
+use crate::models::case::{Case, CaseActionType, CaseChange};
 use crate::models::config::GuildConfig;
-use crate::models::task::Task;
+use crate::models::export::CaseExportFilter;
+use crate::models::task::{Task, TaskAction};
+use crate::models::top::TopArchive;
 use crate::database::redis::RedisConnection;
+use crate::ok_or_skip_without_clone;
 use crate::utils::errors::Error;
 
+/// Name of the unique index on case numbers, its existence marks the cases as migrated
+const CASES_INDEX_NAME: &str = "guild_id_index_unique";
+/// Document in the migrations collection locking the case index migration, so only one process runs it
+const CASES_MIGRATION_LOCK: &str = "case_indexes";
+/// The lock is released after this time when the process running the migration stopped
+const CASES_MIGRATION_LOCK_DURATION: i64 = 10 * 60 * 1000;
+/// MongoDB error code of a duplicate key
+const DUPLICATE_KEY_CODE: i32 = 11000;
+
+#[derive(Deserialize)]
+struct GuildMaxIndex {
+    #[serde(rename = "_id")]
+    guild_id: Id<GuildMarker>,
+    max: i64
+}
+
+#[derive(Deserialize)]
+struct DuplicatedIndex {
+    #[serde(rename = "_id")]
+    key: DuplicatedIndexKey,
+    ids: Vec<ObjectId>
+}
+
+#[derive(Deserialize)]
+struct DuplicatedIndexKey {
+    guild_id: Id<GuildMarker>
+}
+
 #[derive(Clone)]
 pub struct MongoDBConnection {
     pub client: Client,
     pub database: Database,
     pub cases: Collection<Case>,
+    pub counters: Collection<Document>,
+    /// Locks and states of data migrations
+    pub migrations: Collection<Document>,
     pub configs: Collection<GuildConfig>,
     #[cfg(any(feature = "tasks", feature = "custom-clients"))]
     pub clients: Collection<ClientData>,
     pub tasks: Collection<Task>,
+    pub top_archives: Collection<TopArchive>,
     pub configs_cache: Arc<DashMap<Id<GuildMarker>, GuildConfig>>
 }
 
@@ -35,19 +73,25 @@ impl MongoDBConnection {
         let db = client.database("custom");
         let configs = db.collection::<GuildConfig>("configs");
         let cases = db.collection("cases");
+        let counters = db.collection("counters");
+        let migrations = db.collection("migrations");
         #[cfg(any(feature = "tasks", feature = "custom-clients"))]
         let clients = db.collection("clients");
         let tasks = db.collection("tasks");
+        let top_archives = db.collection("top_archives");
 
         Ok(Self {
             configs_cache: Arc::new(DashMap::new()),
             database: db,
             cases,
+            counters,
+            migrations,
             client,
             #[cfg(any(feature = "tasks", feature = "custom-clients"))]
             clients,
             configs,
-            tasks
+            tasks,
+            top_archives
         })
     }
 
@@ -58,12 +102,14 @@ impl MongoDBConnection {
                 Ok(config.to_owned())
             },
             None => {
-                let config = self.configs.clone_with_type().find_one(
+                let mut config: GuildConfig = self.configs.clone_with_type().find_one(
                     doc! {
                         "guild_id": guild_id.to_string()
                     }, None
                 ).await?.unwrap_or_else(|| GuildConfig::new(guild_id));
 
+                if let Some(automod) = config.moderation.automod.as_mut() { automod.compile() }
+
                 self.configs_cache.insert(guild_id, config.to_owned());
 
                 Ok(config)
@@ -72,6 +118,41 @@ impl MongoDBConnection {
 
     }
 
+    /// Validates and saves the config, then tells all processes to drop their cached copies
+    pub async fn save_config(&self, redis: &RedisConnection, config: GuildConfig) -> Result<(), Error> {
+        config.validate()?;
+
+        let guild_id = config.guild_id;
+        self.configs.replace_one(
+            doc! { "guild_id": guild_id.to_string() },
+            config,
+            ReplaceOptions::builder().upsert(true).build()
+        ).await.map_err(Error::from)?;
+
+        self.configs_cache.remove(&guild_id);
+        redis.notify_config_change(guild_id).await.ok();
+        Ok(())
+    }
+
+    /// Removes configs from the cache when their changes are published, reconnects when the subscription ends
+    pub fn listen_for_config_changes(&self, redis: RedisConnection) {
+        let configs_cache = self.configs_cache.to_owned();
+        tokio::spawn(async move {
+            loop {
+                if let Ok(pubsub) = redis.subscribe_config_changes().await {
+                    let mut messages = pubsub.into_on_message();
+                    while let Some(message) = messages.next().await {
+                        let guild_id = ok_or_skip_without_clone!(message.get_payload::<String>(), Ok);
+                        let guild_id = ok_or_skip_without_clone!(Id::<GuildMarker>::from_str(&guild_id), Ok);
+                        configs_cache.remove(&guild_id);
+                    }
+                }
+
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+        });
+    }
+
     pub async fn create_case(
         &self,
         discord_http: Arc<twilight_http::Client>,
@@ -106,23 +187,297 @@ impl MongoDBConnection {
 
     }
 
+    /// Applies `set` to the case, records the change in the case history and posts it to the logs channel
+    pub async fn update_case(
+        &self,
+        discord_http: Arc<twilight_http::Client>,
+        config: &GuildConfig,
+        index: i64,
+        removed: bool,
+        set: Document,
+        change: CaseChange
+    ) -> Result<Case, Error> {
+        let guild_id = config.guild_id;
+        let history_entry = mongodb::bson::to_bson(&change).map_err(|_| "Cannot serialize the case change")?;
+
+        let case = self.cases.find_one_and_update(
+            doc! { "guild_id": guild_id.to_string(), "index": index, "removed": removed },
+            doc! { "$set": set, "$push": { "history": history_entry } },
+            FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build()
+        ).await.map_err(Error::from)?.ok_or("Cannot find case with selected id")?;
+
+        // the change is already saved, so a failed log message doesn't fail the edit
+        if let Some(channel_id) = config.moderation.logs_channel {
+            let result = match discord_http.create_message(channel_id).embeds(&[case.to_change_embed(&change)]) {
+                Ok(message) => message.await.map(|_| ()).map_err(|error| error.to_string()),
+                Err(error) => Err(error.to_string())
+            };
+            if let Err(error) = result {
+                eprintln!("Cannot log change of case {index} in guild {guild_id}: {error}");
+            }
+        }
+
+        Ok(case)
+    }
+
     pub async fn get_next_case_index(&self, guild_id: Id<GuildMarker>) -> Result<u64, Error> {
-        Ok(self.cases.count_documents(
-            doc! { "guild_id": guild_id.to_string() }, None
-        ).await.map_err(Error::from)? + 1)
+        let counter = self.counters.find_one_and_update(
+            doc! { "_id": guild_id.to_string() },
+            doc! { "$inc": { "cases": 1_i64 } },
+            FindOneAndUpdateOptions::builder()
+                .upsert(true)
+                .return_document(ReturnDocument::After)
+                .build()
+        ).await.map_err(Error::from)?.ok_or("Cannot increase the case counter")?;
+
+        counter.get_i64("cases")
+            .map(|index| index as u64)
+            .map_err(|_| Error::from("Invalid case counter"))
     }
 
-    pub async fn create_task(&self, task: Task) -> Result<(), Error> {
-        self.tasks.insert_one(task, None).await.map(|_| ()).map_err(Error::from)
+    /// Gives new numbers to cases sharing one, initializes case counters
+    /// from existing cases and creates the unique index on case numbers
+    pub async fn migrate_case_indexes(&self) -> Result<(), Error> {
+        let indexes = self.cases.list_index_names().await.unwrap_or_default();
+        if indexes.iter().any(|name| name == CASES_INDEX_NAME) { return Ok(()) }
+
+        // when the lock is held or the migration is done, the filter doesn't match and the upsert fails on the _id
+        let now = DateTime::now().timestamp_millis();
+        let lock = self.migrations.update_one(
+            doc! {
+                "_id": CASES_MIGRATION_LOCK,
+                "done": { "$ne": true },
+                "locked_until": { "$lt": DateTime::from_millis(now) }
+            },
+            doc! { "$set": { "locked_until": DateTime::from_millis(now + CASES_MIGRATION_LOCK_DURATION) } },
+            UpdateOptions::builder().upsert(true).build()
+        ).await;
+
+        match lock {
+            Ok(_) => {},
+            Err(error) if is_duplicate_key(&error) => return Ok(()),
+            Err(error) => return Err(Error::from(error))
+        }
+
+        let mut max_indexes = self.cases.aggregate([
+            doc! { "$group": { "_id": "$guild_id", "max": { "$max": "$index" } } }
+        ], None).await.map_err(Error::from)?;
+
+        while let Some(result) = max_indexes.try_next().await.map_err(Error::from)? {
+            let guild = mongodb::bson::from_document::<GuildMaxIndex>(result).map_err(Error::from)?;
+            self.counters.update_one(
+                doc! { "_id": guild.guild_id.to_string() },
+                doc! { "$max": { "cases": guild.max } },
+                UpdateOptions::builder().upsert(true).build()
+            ).await.map_err(Error::from)?;
+        }
+
+        let mut duplicates = self.cases.aggregate([
+            doc! { "$sort": { "created_at": 1_i32 } },
+            doc! { "$group": {
+                "_id": { "guild_id": "$guild_id", "index": "$index" },
+                "ids": { "$push": "$_id" },
+                "count": { "$sum": 1_i32 }
+            } },
+            doc! { "$match": { "count": { "$gt": 1_i32 } } }
+        ], None).await.map_err(Error::from)?;
+
+        while let Some(result) = duplicates.try_next().await.map_err(Error::from)? {
+            let duplicate = mongodb::bson::from_document::<DuplicatedIndex>(result).map_err(Error::from)?;
+            // the oldest case keeps its number
+            for id in duplicate.ids.into_iter().skip(1) {
+                let index = self.get_next_case_index(duplicate.key.guild_id).await?;
+                self.cases.update_one(
+                    doc! { "_id": id },
+                    doc! { "$set": { "index": index as i64 } },
+                    None
+                ).await.map_err(Error::from)?;
+            }
+        }
+
+        self.cases.create_index(
+            IndexModel::builder()
+                .keys(doc! { "guild_id": 1_i32, "index": 1_i32 })
+                .options(
+                    IndexOptions::builder()
+                        .unique(true)
+                        .name(CASES_INDEX_NAME.to_string())
+                        .build()
+                )
+                .build(),
+            None
+        ).await.map_err(Error::from)?;
+
+        self.migrations.update_one(
+            doc! { "_id": CASES_MIGRATION_LOCK },
+            doc! { "$set": { "done": true } },
+            None
+        ).await.map_err(Error::from)?;
+
+        Ok(())
+    }
+
+    /// Returns cases of the member with the given action which weren't removed and didn't expire
+    pub async fn get_active_member_cases(
+        &self,
+        guild_id: Id<GuildMarker>,
+        member_id: Id<UserMarker>,
+        action: CaseActionType
+    ) -> Result<Vec<Case>, Error> {
+        self.cases.find(
+            doc! {
+                "guild_id": guild_id.to_string(),
+                "member_id": member_id.to_string(),
+                "action": u8::from(action) as i64,
+                "removed": false,
+                "$or": [{ "expires_at": null }, { "expires_at": { "$gt": DateTime::now() } }]
+            },
+            FindOptions::builder().sort(doc! { "index": 1_i32 }).build()
+        ).await.map_err(Error::from)?.try_collect().await.map_err(Error::from)
+    }
+
+    /// Returns the newest case of the member with the given action which wasn't removed
+    pub async fn get_last_case(
+        &self,
+        guild_id: Id<GuildMarker>,
+        member_id: Id<UserMarker>,
+        action: CaseActionType
+    ) -> Result<Option<Case>, Error> {
+        self.cases.find_one(
+            doc! {
+                "guild_id": guild_id.to_string(),
+                "member_id": member_id.to_string(),
+                "action": u8::from(action) as i64,
+                "removed": false
+            },
+            FindOneOptions::builder().sort(doc! { "index": -1_i32 }).build()
+        ).await.map_err(Error::from)
+    }
+
+    /// Returns all cases matching the filter, including removed ones, the oldest first
+    pub async fn export_cases(&self, guild_id: Id<GuildMarker>, filter: &CaseExportFilter) -> Result<Cursor<Case>, Error> {
+        self.cases.find(
+            filter.to_document(guild_id),
+            FindOptions::builder().sort(doc! { "index": 1_i32 }).build()
+        ).await.map_err(Error::from)
+    }
+
+    pub async fn create_task(&self, redis: &RedisConnection, task: Task) -> Result<(), Error> {
+        let execute_at = task.execute_at.timestamp_millis();
+        self.tasks.insert_one(task, None).await.map_err(Error::from)?;
+        // the scheduler may sleep past the task, so it has to be woken up
+        redis.notify_task(execute_at).await.ok();
+        Ok(())
+    }
+
+    /// Returns pending tasks of the guild, the earliest first
+    pub async fn get_guild_tasks(&self, guild_id: Id<GuildMarker>, limit: i64) -> Result<Vec<Task>, Error> {
+        self.tasks.find(
+            doc! { "guild_id": guild_id.to_string(), "failed_at": null },
+            FindOptions::builder().sort(doc! { "execute_at": 1_i32 }).limit(limit).build()
+        ).await.map_err(Error::from)?.try_collect().await.map_err(Error::from)
+    }
+
+    /// Deletes the task when it belongs to the guild, returns `false` when nothing was deleted
+    pub async fn delete_guild_task(&self, guild_id: Id<GuildMarker>, id: ObjectId) -> Result<bool, Error> {
+        self.tasks.delete_one(
+            doc! { "_id": id, "guild_id": guild_id.to_string() }, None
+        ).await.map(|result| result.deleted_count > 0).map_err(Error::from)
+    }
+
+    /// Deletes pending tasks of the guild with the given action and returns how many were deleted
+    pub async fn delete_tasks(&self, guild_id: Id<GuildMarker>, action: TaskAction) -> Result<u64, Error> {
+        let action = mongodb::bson::to_bson(&action).map_err(|_| "Cannot serialize the task action")?;
+        self.tasks.delete_many(
+            doc! { "guild_id": guild_id.to_string(), "action": action }, None
+        ).await.map(|result| result.deleted_count).map_err(Error::from)
     }
 
     #[cfg(feature = "tasks")]
-    pub async fn get_and_delete_future_tasks(&self, after: u64) -> Result<Vec<Task>, Error> {
-        let time = DateTime::from_millis(DateTime::now().timestamp_millis() + after as i64);
-        let filter = doc! { "execute_at": { "$lt": time } };
-        let tasks = self.tasks.find(filter.to_owned(), None)
-            .await.map_err(Error::from)?.try_collect().await.map_err(Error::from);
-        self.tasks.delete_many(filter, None).await.map_err(Error::from)?;
-        tasks
-    }
-}
\ No newline at end of file
+    pub async fn get_configs_with_top(&self, period: &str) -> Result<Vec<GuildConfig>, Error> {
+        let mut filter = Document::new();
+        filter.insert(format!("top.{period}"), true);
+        self.configs.find(filter, None)
+            .await.map_err(Error::from)?.try_collect().await.map_err(Error::from)
+    }
+
+    /// Claims one due task for `lease` milliseconds, so other schedulers skip it until the lease ends
+    #[cfg(feature = "tasks")]
+    pub async fn claim_due_task(&self, lease: i64) -> Result<Option<Task>, Error> {
+        let now = DateTime::now();
+        self.tasks.find_one_and_update(
+            doc! {
+                "execute_at": { "$lte": now },
+                "failed_at": null,
+                "$or": [{ "locked_until": null }, { "locked_until": { "$lte": now } }]
+            },
+            doc! {
+                "$set": { "locked_until": DateTime::from_millis(now.timestamp_millis() + lease) }
+            },
+            FindOneAndUpdateOptions::builder()
+                .sort(doc! { "execute_at": 1_i32 })
+                .return_document(ReturnDocument::After)
+                .build()
+        ).await.map_err(Error::from)
+    }
+
+    /// Returns the execution time of the earliest task which isn't claimed
+    #[cfg(feature = "tasks")]
+    pub async fn get_next_task_time(&self) -> Result<Option<DateTime>, Error> {
+        let task = self.tasks.find_one(
+            doc! { "locked_until": null, "failed_at": null },
+            FindOneOptions::builder().sort(doc! { "execute_at": 1_i32 }).build()
+        ).await.map_err(Error::from)?;
+        Ok(task.map(|task| task.execute_at))
+    }
+
+    #[cfg(feature = "tasks")]
+    pub async fn complete_task(&self, id: ObjectId) -> Result<(), Error> {
+        self.tasks.delete_one(doc! { "_id": id }, None)
+            .await.map(|_| ()).map_err(Error::from)
+    }
+
+    /// Keeps the claim of a task which is still running for `lease` milliseconds from now
+    #[cfg(feature = "tasks")]
+    pub async fn extend_task_lease(&self, id: ObjectId, lease: i64) -> Result<(), Error> {
+        let locked_until = DateTime::from_millis(DateTime::now().timestamp_millis() + lease);
+        self.tasks.update_one(
+            doc! { "_id": id },
+            doc! { "$set": { "locked_until": locked_until } },
+            None
+        ).await.map(|_| ()).map_err(Error::from)
+    }
+
+    /// Marks the task as failed, so it isn't executed again but stays as a record of the failure
+    #[cfg(feature = "tasks")]
+    pub async fn fail_task(&self, id: ObjectId) -> Result<(), Error> {
+        self.tasks.update_one(
+            doc! { "_id": id },
+            doc! {
+                "$set": { "failed_at": DateTime::now(), "locked_until": null },
+                "$inc": { "attempts": 1_i64 }
+            },
+            None
+        ).await.map(|_| ()).map_err(Error::from)
+    }
+
+    /// Releases the claim and schedules the task again
+    #[cfg(feature = "tasks")]
+    pub async fn retry_task(&self, id: ObjectId, execute_at: DateTime) -> Result<(), Error> {
+        self.tasks.update_one(
+            doc! { "_id": id },
+            doc! {
+                "$set": { "execute_at": execute_at, "locked_until": null },
+                "$inc": { "attempts": 1_i64 }
+            },
+            None
+        ).await.map(|_| ()).map_err(Error::from)
+    }
+}
+
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(error)) if error.code == DUPLICATE_KEY_CODE
+    )
+}
@@ -1,4 +1,4 @@
-use redis::{Client, RedisError};
+use redis::{Client, ExistenceCheck, RedisError, Script, SetExpiry, SetOptions};
 use serde_json::json;
 use twilight_model::id::marker::{GuildMarker, RoleMarker, UserMarker};
 use twilight_model::id::Id;
@@ -6,6 +6,7 @@ use twilight_model::util::ImageHash;
 use serde::{Serialize, Deserialize};
 use crate::utils::errors::Error;
 use redis::AsyncCommands;
+use redis::aio::PubSub;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PartialGuild {
@@ -14,6 +15,35 @@ pub struct PartialGuild {
     pub roles: Vec<Id<RoleMarker>>
 }
 
+/// Removes expired entries, adds the new one and returns the sum of amounts in the window.
+/// Members are stored as `{amount}:{unique id}` with the expiration time as a score.
+const SLIDING_WINDOW_SCRIPT: &str = r"
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', ARGV[1])
+redis.call('ZADD', KEYS[1], ARGV[2], ARGV[3])
+local last = redis.call('ZRANGE', KEYS[1], -1, -1, 'WITHSCORES')
+redis.call('PEXPIREAT', KEYS[1], last[2])
+local sum = 0
+for _, entry in ipairs(redis.call('ZRANGE', KEYS[1], 0, -1)) do
+    sum = sum + tonumber(string.match(entry, '^(%d+):'))
+end
+return sum
+";
+
+/// Moves the sorted set to the archiving key, unless an archive which wasn't finished is still there,
+/// and returns the content of the archiving key
+const START_ARCHIVE_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[2]) == 0 then
+    if redis.call('EXISTS', KEYS[1]) == 0 then return {} end
+    redis.call('RENAME', KEYS[1], KEYS[2])
+end
+return redis.call('ZREVRANGE', KEYS[2], 0, -1, 'WITHSCORES')
+";
+
+/// Channel where execution times of newly created tasks are published
+const TASKS_CHANNEL: &str = "tasks";
+/// Channel where IDs of guilds with changed configs are published
+const CONFIGS_CHANNEL: &str = "configs";
+
 #[derive(Clone)]
 pub struct RedisConnection {
     pub client: Client,
@@ -73,6 +103,43 @@ impl RedisConnection {
         connection.zrevrange_withscores(path, 0, limit - 1).await
     }
 
+    /// Returns all entries of the sorted set, ordered from the highest score
+    pub async fn get_whole(&self, path: String) -> Result<Vec<(String, u32)>, RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        connection.zrevrange_withscores(path, 0, -1).await
+    }
+
+    pub async fn get_range(
+        &self,
+        path: String,
+        start: isize,
+        limit: isize
+    ) -> Result<Vec<(String, u32)>, RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        connection.zrevrange_withscores(path, start, start + limit - 1).await
+    }
+
+    pub async fn count(&self, path: String) -> Result<usize, RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        connection.zcard(path).await
+    }
+
+    /// Moves the sorted set out of `path` and returns its content, so counting starts again from zero.
+    /// The content is kept until `finish_archive` is called, so an archive which failed is returned again next time
+    pub async fn start_archive(&self, path: String) -> Result<Vec<(String, u32)>, RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        Script::new(START_ARCHIVE_SCRIPT)
+            .key(&path)
+            .key(format!("{path}.archiving"))
+            .invoke_async(&mut connection)
+            .await
+    }
+
+    pub async fn finish_archive(&self, path: String) -> Result<(), RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        connection.del(format!("{path}.archiving")).await
+    }
+
     pub async fn increase(
         &self,
         path: String,
@@ -82,4 +149,69 @@ impl RedisConnection {
         let mut connection = self.client.get_async_connection().await?;
         connection.zincr(path, user_id.to_string(), count).await
     }
-}
\ No newline at end of file
+
+    pub async fn add_xp(
+        &self,
+        path: String,
+        user_id: Id<UserMarker>,
+        xp: u32,
+    ) -> Result<u64, RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        connection.zincr(path, user_id.to_string(), xp).await
+    }
+
+    /// Sets the key only when it doesn't exist yet, returns `true` when the key was set
+    pub async fn set_cooldown(&self, path: String, seconds: usize) -> Result<bool, RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::EX(seconds));
+        let result: Option<String> = connection.set_options(path, 1, options).await?;
+        Ok(result.is_some())
+    }
+
+    /// Adds `amount` which expires at `expires_at` (unix timestamp in milliseconds) to the sliding window
+    /// and returns the sum of entries that haven't expired yet
+    pub async fn add_to_window(
+        &self,
+        path: String,
+        amount: u64,
+        now: i64,
+        expires_at: i64,
+        id: u64
+    ) -> Result<u64, RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        let sum = Script::new(SLIDING_WINDOW_SCRIPT)
+            .key(path)
+            .arg(now)
+            .arg(expires_at)
+            .arg(format!("{amount}:{id}"))
+            .invoke_async(&mut connection)
+            .await?;
+        Ok(sum)
+    }
+
+    /// Tells schedulers that a task which has to be executed at `execute_at` (unix timestamp in milliseconds) was created
+    pub async fn notify_task(&self, execute_at: i64) -> Result<(), RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        connection.publish(TASKS_CHANNEL, execute_at).await
+    }
+
+    pub async fn subscribe_tasks(&self) -> Result<PubSub, RedisError> {
+        let mut pubsub = self.client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(TASKS_CHANNEL).await?;
+        Ok(pubsub)
+    }
+
+    /// Tells all processes that the config of the guild was changed, so it has to be loaded again
+    pub async fn notify_config_change(&self, guild_id: Id<GuildMarker>) -> Result<(), RedisError> {
+        let mut connection = self.client.get_async_connection().await?;
+        connection.publish(CONFIGS_CHANNEL, guild_id.to_string()).await
+    }
+
+    pub async fn subscribe_config_changes(&self) -> Result<PubSub, RedisError> {
+        let mut pubsub = self.client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(CONFIGS_CHANNEL).await?;
+        Ok(pubsub)
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use dashmap::DashMap;
+use tokio::time::Instant;
+use twilight_model::channel::Message;
+use twilight_model::id::Id;
+use twilight_model::id::marker::{ChannelMarker, GuildMarker, UserMarker};
+use crate::utils::normalize::normalize;
+
+/// Number of `DuplicateStore::add` calls between removing expired entries of all users
+const SWEEP_INTERVAL: usize = 1024;
+
+struct PostedMessage {
+    posted_at: Instant,
+    expires_at: Instant,
+    hash: u64,
+    channel_id: Id<ChannelMarker>
+}
+
+type PostedMessages = DashMap<(Id<GuildMarker>, Id<UserMarker>), Vec<PostedMessage>>;
+
+/// Hashes of recently posted messages per user, kept in the process memory
+#[derive(Clone, Default)]
+pub struct DuplicateStore {
+    messages: Arc<PostedMessages>,
+    calls: Arc<AtomicUsize>
+}
+
+impl DuplicateStore {
+    /// Remembers the message for `duration` seconds
+    pub fn add(&self, message: &Message, duration: u16) {
+        let Some(guild_id) = message.guild_id else { return };
+        let Some(hash) = content_hash(&message.content) else { return };
+        let now = Instant::now();
+
+        {
+            let mut messages = self.messages.entry((guild_id, message.author.id)).or_default();
+            messages.retain(|message| message.expires_at > now);
+            messages.push(PostedMessage {
+                posted_at: now,
+                expires_at: now + Duration::from_secs(duration as u64),
+                hash,
+                channel_id: message.channel_id
+            });
+        }
+
+        if self.calls.fetch_add(1, Ordering::Relaxed) % SWEEP_INTERVAL == SWEEP_INTERVAL - 1 {
+            self.messages.retain(|_, messages| {
+                messages.retain(|message| message.expires_at > now);
+                !messages.is_empty()
+            });
+        }
+    }
+
+    /// Returns how many times the message content was posted by the author in the last `duration` seconds,
+    /// with `distinct_channels` posts in the same channel are counted once
+    pub fn count(&self, message: &Message, duration: u16, distinct_channels: bool) -> usize {
+        let Some(guild_id) = message.guild_id else { return 0 };
+        let Some(hash) = content_hash(&message.content) else { return 0 };
+        let Some(messages) = self.messages.get(&(guild_id, message.author.id)) else { return 0 };
+
+        let since = Instant::now().checked_sub(Duration::from_secs(duration as u64));
+
+        let posted = messages.iter()
+            .filter(|posted| posted.hash == hash)
+            .filter(|posted| since.map(|since| posted.posted_at >= since).unwrap_or(true));
+
+        if distinct_channels {
+            posted.map(|posted| posted.channel_id).collect::<HashSet<_>>().len()
+        } else { posted.count() }
+    }
+}
+
+/// Content is normalized first, so small changes like added zero-width characters don't make it unique.
+/// Messages without text (e.g. only attachments or stickers) aren't compared
+fn content_hash(content: &str) -> Option<u64> {
+    let normalized = normalize(content.trim());
+    if normalized.trim().is_empty() { return None }
+
+    let mut hasher = DefaultHasher::new();
+    normalized.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::channel::Message;
+    use twilight_model::id::Id;
+    use super::DuplicateStore;
+
+    fn create_message(channel_id: u64, content: &str) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "channel_id": channel_id.to_string(),
+            "guild_id": "1",
+            "author": { "id": "1", "username": "user", "discriminator": "0", "avatar": null },
+            "content": content,
+            "timestamp": "2024-01-01T00:00:00+00:00",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": [],
+            "pinned": false,
+            "type": 0
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_duplicates() {
+        let store = DuplicateStore::default();
+
+        store.add(&create_message(1, "spam"), 10);
+        store.add(&create_message(1, "SPAM\u{200B}"), 10);
+        store.add(&create_message(2, "spam"), 10);
+        store.add(&create_message(2, "other"), 10);
+
+        let message = create_message(3, "spam");
+        assert_eq!(store.count(&message, 10, false), 3);
+        assert_eq!(store.count(&message, 10, true), 2);
+        assert_eq!(store.count(&create_message(1, "unknown"), 10, false), 0);
+
+        let mut message = message;
+        message.guild_id = Some(Id::new(2));
+        assert_eq!(store.count(&message, 10, false), 0);
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_without_content() {
+        let store = DuplicateStore::default();
+
+        store.add(&create_message(1, ""), 10);
+        store.add(&create_message(1, "\u{200B}"), 10);
+        store.add(&create_message(1, " "), 10);
+
+        assert_eq!(store.count(&create_message(1, ""), 10, false), 0);
+        assert!(store.messages.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::Utc;
+use mongodb::bson::DateTime;
 use twilight_model::channel::message::Embed;
 use twilight_model::channel::message::embed::EmbedAuthor;
 use std::sync::Arc;
@@ -6,7 +7,9 @@ use twilight_http::Client;
 use twilight_model::channel::Message;
 use twilight_model::id::Id;
 use twilight_model::id::marker::GuildMarker;
-use crate::bucket::Bucket;
+use crate::context::Context;
+use crate::commands::moderation::escalation;
+use crate::models::case::{Case, CaseActionType};
 use crate::models::config::GuildConfig;
 use crate::models::config::automod::actions::{Timeout, Action};
 use crate::utils::avatars::get_avatar_url;
@@ -70,7 +73,8 @@ async fn send_logs(
     message: Arc<Message>,
     discord_http: Arc<Client>,
     guild_config: Arc<GuildConfig>,
-    reason: String
+    reason: String,
+    details: Option<String>
 ) -> Result<(), ()> {
     let channel = guild_config.moderation.automod.as_ref().ok_or(())?.logs_channel.ok_or(())?;
 
@@ -84,8 +88,9 @@ async fn send_logs(
         }),
         color: None,
         description: Some(format!(
-            "Message ID: {}\nChannel: <#{}>\n Reason: {}",
-            message.id, message.channel_id, reason
+            "Message ID: {}\nChannel: <#{}>\n Reason: {}{}",
+            message.id, message.channel_id, reason,
+            details.map(|details| format!("\n{details}")).unwrap_or_default()
         )),
         fields: vec![],
         footer: None,
@@ -109,6 +114,54 @@ async fn send_logs(
     Ok(())
 }
 
+/// Creates a case with the bot as a moderator
+async fn create_case(
+    context: Arc<Context>,
+    message: Arc<Message>,
+    discord_http: Arc<Client>,
+    guild_config: Arc<GuildConfig>,
+    action: CaseActionType,
+    duration: Option<i64>,
+    reason: String
+) -> Result<(), ()> {
+    let guild_id = guild_config.guild_id;
+    let moderator_id = discord_http.current_user()
+        .await.map_err(|_| ())?
+        .model().await.map_err(|_| ())?.id;
+
+    let index = context.mongodb.get_next_case_index(guild_id).await.map_err(|_| ())?;
+
+    let created_at = DateTime::now();
+    let (expires_at, points) = guild_config.moderation.get_case_expiry(&action, created_at);
+
+    let case = Case {
+        moderator_id,
+        created_at,
+        guild_id,
+        member_id: message.author.id,
+        action,
+        reason: Some(reason),
+        removed: false,
+        duration,
+        index,
+        related_cases: vec![],
+        expires_at,
+        points,
+        history: vec![]
+    };
+
+    let embed = case.to_embed(discord_http.to_owned()).await.map_err(|_| ())?;
+
+    context.mongodb.create_case(
+        discord_http,
+        &context.redis,
+        case,
+        embed,
+        if guild_config.moderation.dm_case { Some(message.author.id) } else { None },
+        guild_config.moderation.logs_channel
+    ).await.map_err(|_| ())
+}
+
 async fn timeout(
     guild_id: Id<GuildMarker>,
     message: Arc<Message>,
@@ -155,47 +208,79 @@ async fn ban(
     Ok(())
 }
 
+/// Runs the action triggered by exceeding a bucket limit, buckets can't increase other buckets
 pub async fn run_bucket_action(
     action: Action,
     message: Arc<Message>,
     discord_http: Arc<Client>,
+    context: Arc<Context>,
     guild_config: Arc<GuildConfig>,
     reason: String
 ) -> Result<(), ()> {
-    let guild_id = message.guild_id.ok_or(())?;
-    match action {
-        Action::DirectMessage => send_direct_message(message, discord_http, reason).await,
-        Action::DeleteMessage => delete_message(message, discord_http).await,
-        Action::SendLogs => send_logs(message, discord_http, guild_config, reason).await,
-        Action::Timeout(config) => timeout(guild_id, message, discord_http, config).await,
-        Action::Kick => kick(guild_id, message, discord_http).await,
-        Action::Ban => ban(guild_id, message, discord_http).await,
-        _ => Ok(())
-    }?;
-
-    Ok(())
+    execute(action, message, discord_http, context, guild_config, reason, None).await
 }
 
+/// `details` describe what the checks found in the message, they are included in the logs
 pub async fn run_action(
     action: Action,
     message: Arc<Message>,
     discord_http: Arc<Client>,
-    bucket: Bucket,
+    context: Arc<Context>,
     guild_config: Arc<GuildConfig>,
-    reason: String
+    reason: String,
+    details: Option<String>
 ) -> Result<(), ()> {
-    let guild_id = message.guild_id.ok_or(())?;
     match action {
-        Action::DirectMessage => send_direct_message(message, discord_http, reason).await,
         Action::IncreaseBucket(data) => {
-            crate::bucket::incr(discord_http, message, guild_config, bucket, data).await;
+            crate::bucket::incr(discord_http, message, guild_config, context, data).await;
             Ok(())
         }
+        _ => execute(action, message, discord_http, context, guild_config, reason, details).await
+    }
+}
+
+async fn execute(
+    action: Action,
+    message: Arc<Message>,
+    discord_http: Arc<Client>,
+    context: Arc<Context>,
+    guild_config: Arc<GuildConfig>,
+    reason: String,
+    details: Option<String>
+) -> Result<(), ()> {
+    let guild_id = message.guild_id.ok_or(())?;
+    match action {
+        Action::DirectMessage => send_direct_message(message, discord_http, reason).await,
         Action::DeleteMessage => delete_message(message, discord_http).await,
-        Action::SendLogs => send_logs(message, discord_http, guild_config, reason).await,
-        Action::Timeout(config) => timeout(guild_id, message, discord_http, config).await,
-        Action::Kick => kick(guild_id, message, discord_http).await,
-        Action::Ban => ban(guild_id, message, discord_http).await
+        Action::SendLogs => send_logs(message, discord_http, guild_config, reason, details).await,
+        Action::Timeout(config) => {
+            let duration = config.duration as i64;
+            timeout(guild_id, message.to_owned(), discord_http.to_owned(), config).await?;
+            create_case(
+                context, message, discord_http, guild_config, CaseActionType::Timeout, Some(duration), reason
+            ).await
+        },
+        Action::Kick => {
+            kick(guild_id, message.to_owned(), discord_http.to_owned()).await?;
+            create_case(
+                context, message, discord_http, guild_config, CaseActionType::Kick, None, reason
+            ).await
+        },
+        Action::Ban => {
+            ban(guild_id, message.to_owned(), discord_http.to_owned()).await?;
+            create_case(
+                context, message, discord_http, guild_config, CaseActionType::Ban, None, reason
+            ).await
+        },
+        Action::Warn => {
+            let member_id = message.author.id;
+            create_case(
+                context.to_owned(), message, discord_http.to_owned(), guild_config.to_owned(),
+                CaseActionType::Warn, None, reason
+            ).await?;
+            escalation::run(context, discord_http, &guild_config, member_id).await.map_err(|_| ())
+        },
+        Action::IncreaseBucket(_) => Ok(())
     }?;
 
     Ok(())
@@ -1,26 +1,86 @@
-use crate::models::config::automod::checks::{CapsLock, Check, Invites, Regex, TextLines};
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use twilight_model::channel::Message;
+use crate::context::Context;
+use crate::duplicates::DuplicateStore;
+use crate::models::config::automod::checks::{
+    CapsLock, Check, Duplicates, Emojis, Invites, Links, Mentions, Regex, TextLines, Zalgo
+};
+use crate::models::config::automod::compiled::MessageMatcher;
 use crate::ok_or_skip_without_clone;
-use crate::links::ScamLinks;
+use crate::links::{find_guild_domain, ScamDomain, ScamLinks};
+use crate::resolver::{is_shortener, LinkResolver};
+use crate::utils::urls::{domain, extract_urls, matches_domain};
+
+static DOMAIN_REGEX: OnceLock<regex::Regex> = OnceLock::new();
+static INVITE_REGEX: OnceLock<regex::Regex> = OnceLock::new();
+static CUSTOM_EMOJI_REGEX: OnceLock<regex::Regex> = OnceLock::new();
+
+/// Check which matched the message
+#[derive(Debug, Default, PartialEq)]
+pub struct CheckMatch {
+    /// What was found in the message, shown in the logs
+    pub details: Option<String>
+}
 
 impl Check {
-    pub async fn is_matching(&self, message_content: &String, scam_domains: &ScamLinks) -> Result<bool, ()> {
-        match self {
-            Check::FlaggedScamLink => Self::flagged_scam_link(message_content, scam_domains).await,
-            Check::TextLines(config) => Ok(Self::text_lines(config, message_content)),
-            Check::CapsLock(config) => Ok(Self::caps_lock(config, message_content)),
+    /// Returns `None` when the check doesn't match, `position` is the (rule index, check index) of compiled checks
+    pub async fn is_matching(
+        &self,
+        message: &Message,
+        context: &Context,
+        matcher: &MessageMatcher<'_>,
+        (rule_index, check_index): (usize, usize)
+    ) -> Option<CheckMatch> {
+        let message_content = &message.content;
+        let is_matching = match self {
+            Check::FlaggedScamLink => {
+                let scam_domain = Self::flagged_scam_link(message_content, &context.scam_domains, matcher.scam_domains())?;
+                return Some(CheckMatch {
+                    details: Some(format!("Scam domain: `{}` (source: {})", scam_domain.domain, scam_domain.source))
+                })
+            },
+            Check::TextLines(config) => Self::text_lines(config, message_content),
+            Check::CapsLock(config) => Self::caps_lock(config, message_content),
             Check::Invites(config) => Self::invites(config, message_content),
-            Check::Regex(config) => Self::regex(config, message_content)
-        }
+            Check::Regex(config) => matcher.is_match(rule_index, check_index)
+                .map(|is_matching| Self::regex(config, is_matching))
+                .unwrap_or(false),
+            Check::Blocklist(_) => {
+                let term = matcher.find_blocklisted(rule_index, check_index)?;
+                return Some(CheckMatch { details: Some(format!("Blocklisted term: `{term}`")) })
+            },
+            Check::Mentions(config) => Self::mentions(config, message),
+            Check::Emojis(config) => Self::emojis(config, message_content),
+            Check::Zalgo(config) => Self::zalgo(config, message_content),
+            Check::Duplicates(config) => {
+                let count = Self::duplicates(config, message, &context.duplicates)?;
+                return Some(CheckMatch { details: Some(format!("Posted {count} times")) })
+            },
+            Check::Links(config) => {
+                let domain = Self::links(config, message_content, context.link_resolver.as_ref()).await?;
+                return Some(CheckMatch { details: Some(format!("Link: `{domain}`")) })
+            }
+        };
+
+        is_matching.then(CheckMatch::default)
     }
 
-    async fn flagged_scam_link(message_content: &str, scam_domains: &ScamLinks) -> Result<bool, ()> {
-        let domains = regex::Regex::new(r"(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z0-9][a-z0-9-]{0,61}[a-z0-9]").map_err(|_| ())?;
+    /// Guild domains are checked only in the guild which listed them
+    fn flagged_scam_link(
+        message_content: &str,
+        scam_domains: &ScamLinks,
+        guild_domains: &HashSet<String>
+    ) -> Option<ScamDomain> {
+        let domains = DOMAIN_REGEX.get_or_init(|| regex::Regex::new(
+            r"(?:[a-z0-9](?:[a-z0-9-]{0,61}[a-z0-9])?\.)+[a-z0-9][a-z0-9-]{0,61}[a-z0-9]"
+        ).expect("Invalid domain regex"));
 
         let message_content = message_content.to_lowercase();
 
         let domains = domains.find_iter(message_content.as_str());
-        let domains = domains.map(|domain| domain.as_str().to_string()).collect();
-        Ok(scam_domains.contains(domains).await)
+        let domains = domains.map(|domain| domain.as_str().to_string()).collect::<Vec<String>>();
+        find_guild_domain(&domains, guild_domains).or_else(|| scam_domains.find(domains))
     }
 
     fn text_lines(config: &TextLines, message_content: &String) -> bool {
@@ -47,8 +107,10 @@ impl Check {
         } else { true })
     }
 
-    fn invites(config: &Invites, message_content: &str) -> Result<bool, ()> {
-        let invites = regex::Regex::new(r"(?i)(discord.gg|discordapp.com/invite|discord.com/invite)(?:/#)?/([a-zA-Z0-9-]+)").map_err(|_| ())?;
+    fn invites(config: &Invites, message_content: &str) -> bool {
+        let invites = INVITE_REGEX.get_or_init(|| regex::Regex::new(
+            r"(?i)(discord.gg|discordapp.com/invite|discord.com/invite)(?:/#)?/([a-zA-Z0-9-]+)"
+        ).expect("Invalid invite regex"));
 
         let message_content = message_content.to_lowercase();
 
@@ -62,19 +124,130 @@ impl Check {
             }
         }
 
-        Ok(contains)
+        contains
+    }
+
+    fn regex(config: &Regex, is_matching: bool) -> bool {
+        (is_matching && config.is_matching) || (!is_matching && !config.is_matching)
+    }
+
+    fn mentions(config: &Mentions, message: &Message) -> bool {
+        let roles = message.mention_roles.len();
+        let everyone = message.content.matches("@everyone").count() + message.content.matches("@here").count();
+
+        config.roles.map(|limit| roles >= limit as usize).unwrap_or(false)
+            || config.everyone.map(|limit| everyone >= limit as usize).unwrap_or(false)
+    }
+
+    fn emojis(config: &Emojis, message_content: &str) -> bool {
+        let custom_emojis = CUSTOM_EMOJI_REGEX.get_or_init(|| regex::Regex::new(
+            r"<a?:\w{2,32}:\d+>"
+        ).expect("Invalid custom emoji regex"));
+
+        let emojis = custom_emojis.find_iter(message_content).count() + count_unicode_emojis(message_content);
+
+        (if let Some(min) = config.min {
+            emojis > (min as usize)
+        } else { true }) && (if let Some(max) = config.max {
+            emojis < (max as usize)
+        } else { true })
+    }
+
+    fn zalgo(config: &Zalgo, message_content: &str) -> bool {
+        let combining = message_content.chars().filter(|char| is_combining(*char)).count();
+        if combining == 0 { return false }
+
+        let other = message_content.chars().filter(|char| !is_combining(*char) && !char.is_whitespace()).count();
+        combining * 100 / other.max(1) > config.min as usize
+    }
+
+    /// Returns how many times the content was posted when it's at least the configured count
+    fn duplicates(config: &Duplicates, message: &Message, store: &DuplicateStore) -> Option<usize> {
+        let count = store.count(message, config.duration, config.distinct_channels);
+        (count >= config.count as usize).then_some(count)
+    }
+
+    /// Returns the domain of the first link which isn't allowed
+    async fn links(config: &Links, message_content: &str, resolver: &dyn LinkResolver) -> Option<String> {
+        for url in extract_urls(message_content) {
+            let url = if config.resolve_shorteners && is_shortener(&url) {
+                // the shortener domain is checked when the link cannot be resolved
+                resolver.resolve(&url).await.unwrap_or(url)
+            } else { url };
+
+            let domain = ok_or_skip_without_clone!(domain(&url), Some);
+            if !is_domain_allowed(config, &domain) { return Some(domain) }
+        }
+
+        None
     }
+}
+
+fn is_domain_allowed(config: &Links, domain: &str) -> bool {
+    let longest_match = |domains: &Vec<String>| domains.iter()
+        .filter(|pattern| matches_domain(domain, &pattern.to_lowercase()))
+        .map(String::len)
+        .max();
+
+    match (longest_match(&config.allowed), longest_match(&config.denied)) {
+        (Some(allowed), Some(denied)) => allowed >= denied,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => !config.allowlist_only
+    }
+}
+
+fn is_combining(char: char) -> bool {
+    matches!(
+        char,
+        '\u{0300}'..='\u{036F}' | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}' | '\u{FE20}'..='\u{FE2F}'
+    )
+}
 
-    fn regex(config: &Regex, message_content: &str) -> Result<bool, ()> {
-        let regex = regex::Regex::new(&config.regex).map_err(|_| ())?;
-        let is_matching = regex.is_match(message_content);
-        Ok((is_matching && config.is_matching) || (!is_matching && !config.is_matching))
+/// Approximate count, characters joined with a zero-width joiner and flags are counted as one emoji
+fn count_unicode_emojis(message_content: &str) -> usize {
+    let mut count = 0;
+    let mut previous = None;
+    let mut regional_indicators = 0;
+
+    for char in message_content.chars() {
+        let is_joined = previous == Some('\u{200D}');
+        previous = Some(char);
+
+        match char {
+            // skin tone modifiers
+            '\u{1F3FB}'..='\u{1F3FF}' => {},
+            '\u{1F1E6}'..='\u{1F1FF}' => {
+                regional_indicators += 1;
+                if regional_indicators % 2 == 1 { count += 1 }
+            },
+            '\u{1F300}'..='\u{1F64F}' | '\u{1F680}'..='\u{1F6FF}' | '\u{1F900}'..='\u{1F9FF}'
+                | '\u{1FA70}'..='\u{1FAFF}' | '\u{2600}'..='\u{27BF}' if !is_joined => count += 1,
+            _ => {}
+        }
     }
+
+    count
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::models::config::automod::checks::{CapsLock, Invites, Regex, TextLines, Check};
+    use std::collections::HashMap;
+    use async_trait::async_trait;
+    use reqwest::Url;
+    use crate::models::config::automod::checks::{CapsLock, Emojis, Invites, Links, Regex, TextLines, Check, Zalgo};
+    use crate::resolver::LinkResolver;
+
+    /// Resolves links from a map instead of sending requests
+    struct StubResolver(HashMap<&'static str, &'static str>);
+
+    #[async_trait]
+    impl LinkResolver for StubResolver {
+        async fn resolve(&self, url: &Url) -> Option<Url> {
+            self.0.get(url.as_str()).and_then(|target| Url::parse(target).ok())
+        }
+    }
 
     #[test]
     fn test_invites() {
@@ -82,27 +255,27 @@ mod tests {
             Check::invites(
                 &Invites { allowed_invites: vec![] },
                 &"discord.gg/discord-developers".to_string()
-            ).unwrap(),
+            ),
             true
         );
         assert_eq!(
             Check::invites(
                 &Invites { allowed_invites: vec![] },
                 &"discord.com/invite/discord-developers".to_string()
-            ).unwrap(),
+            ),
             true
         );
         assert_eq!(
             Check::invites(
                 &Invites { allowed_invites: vec!["discord-developers".to_string()] },
                 &"discord.gg/discord-developers".to_string()
-            ).unwrap(),
+            ),
             false
         );
         assert_eq!(
             Check::invites(
                 &Invites { allowed_invites: vec![] }, &"".to_string()
-            ).unwrap(),
+            ),
             false
         );
     }
@@ -126,24 +299,15 @@ mod tests {
     #[test]
     fn test_regexp_matching() {
         assert_eq!(
-            Check::regex(
-                &Regex { is_matching: true, regex: "ok".to_string() },
-                &"ok".to_string()
-            ).unwrap(),
+            Check::regex(&Regex { is_matching: true, regex: "ok".to_string() }, true),
             true
         );
         assert_eq!(
-            Check::regex(
-                &Regex { is_matching: false, regex: "ok".to_string() },
-                &"ok".to_string()
-            ).unwrap(),
+            Check::regex(&Regex { is_matching: false, regex: "ok".to_string() }, true),
             false
         );
         assert_eq!(
-            Check::regex(
-                &Regex { is_matching: true, regex: "no".to_string() },
-                &"ok".to_string()
-            ).unwrap(),
+            Check::regex(&Regex { is_matching: true, regex: "no".to_string() }, false),
             false
         );
     }
@@ -173,4 +337,40 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_emojis() {
+        let config = Emojis { min: Some(2), max: None };
+        assert!(!Check::emojis(&config, "hi 👋 <:custom:123>"));
+        assert!(Check::emojis(&config, "👋🏽 <a:custom:123> 🇵🇱"));
+        assert!(!Check::emojis(&config, "👨\u{200D}👩\u{200D}👧 🇵🇱"));
+    }
+
+    #[test]
+    fn test_zalgo() {
+        let config = Zalgo { min: 50 };
+        assert!(!Check::zalgo(&config, "hello"));
+        assert!(!Check::zalgo(&config, "zalgó"));
+        assert!(Check::zalgo(&config, "h̸̢e̵l̷l̴o̶"));
+    }
+
+    #[tokio::test]
+    async fn test_links() {
+        let resolver = StubResolver(HashMap::from([("https://bit.ly/abc", "https://evil.com/login")]));
+        let config = Links {
+            allowed: vec!["docs.example.com".to_string()],
+            denied: vec!["example.com".to_string(), "evil.com".to_string()],
+            allowlist_only: false,
+            resolve_shorteners: true
+        };
+
+        let links = |content: &'static str| Check::links(&config, content, &resolver);
+        assert_eq!(links("https://cdn.example.com/file").await, Some("cdn.example.com".to_string()));
+        assert_eq!(links("[docs](https://docs.example.com)").await, None);
+        assert_eq!(links("https://bit.ly/abc").await, Some("evil.com".to_string()));
+        assert_eq!(links("https://bit.ly/unknown https://other.com").await, None);
+
+        let config = Links { allowlist_only: true, ..config };
+        assert_eq!(Check::links(&config, "https://other.com", &resolver).await, Some("other.com".to_string()));
+        assert_eq!(Check::links(&config, "https://docs.example.com", &resolver).await, None);
+    }
 }
\ No newline at end of file
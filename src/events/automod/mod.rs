@@ -50,28 +50,46 @@ pub async fn run(
     if is_ignored(&message, &automod_config.ignore) { return Ok(()) }
 
     let message = Arc::new(message);
+    let compiled = automod_config.compiled();
+    let matcher = compiled.matcher(&message.content);
 
-    for automod_rule in &automod_config.rules {
+    // the message is remembered before running checks, so it's also counted by them
+    if let Some(duration) = compiled.duplicates_duration().filter(|_| triger == TrigerEvent::MessageCreate) {
+        context.duplicates.add(&message, duration);
+    }
+
+    for (rule_index, automod_rule) in automod_config.rules.iter().enumerate() {
         if triger == TrigerEvent::MessageUpdate && !automod_rule.check_on_edit { continue }
+        // rules with invalid patterns could only be saved before validation was added
+        if !compiled.is_rule_valid(rule_index) { continue }
         if is_ignored(&message, &automod_rule.ignore) { continue }
 
         for filter_meta in &automod_rule.filters {
             if filter_meta.filter.is_matching(&message) != filter_meta.negate { return Ok(()) }
         }
 
-        for check in &automod_rule.checks {
-            let is_allowed = check.is_matching(&message.content, &context.scam_domains).await?;
-            if !is_allowed { return Ok(()) }
+        let mut details = vec![];
+        for (check_index, check) in automod_rule.checks.iter().enumerate() {
+            let check_match = check.is_matching(
+                &message, &context, &matcher, (rule_index, check_index)
+            ).await;
+
+            match check_match {
+                Some(check_match) => details.extend(check_match.details),
+                None => return Ok(())
+            }
         }
+        let details = (!details.is_empty()).then(|| details.join("\n"));
 
         for action in &automod_rule.actions {
             let run = run_action(
                 action.action.to_owned(),
                 message.to_owned(),
                 discord_http.to_owned(),
-                context.bucket.to_owned(),
+                context.to_owned(),
                 guild_config.to_owned(),
                 automod_rule.reason.to_owned(),
+                details.to_owned()
             );
 
             if action.sync {
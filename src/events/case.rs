@@ -3,17 +3,22 @@ use mongodb::bson::DateTime;
 use twilight_http::Client;
 use twilight_model::gateway::payload::incoming::GuildAuditLogEntryCreate;
 use twilight_model::guild::audit_log::{AuditLogChange, AuditLogEventType};
+use twilight_model::id::Id;
+use twilight_model::id::marker::RoleMarker;
 use twilight_util::snowflake::Snowflake;
 use crate::context::Context;
 use crate::models::case::{Case, CaseActionType};
 
 pub async fn run(event: Box<GuildAuditLogEntryCreate>, discord_http: Arc<Client>, context: Arc<Context>) -> Result<(), ()> {
-    let action_type = match event.action_type {
-        AuditLogEventType::MemberKick => CaseActionType::Kick,
-        AuditLogEventType::MemberBanAdd => CaseActionType::Ban,
-        AuditLogEventType::MemberUpdate => CaseActionType::Timeout,
-        _ => return Ok(())
-    };
+    if ![
+        AuditLogEventType::MemberKick,
+        AuditLogEventType::MemberBanAdd,
+        AuditLogEventType::MemberBanRemove,
+        AuditLogEventType::MemberUpdate,
+        AuditLogEventType::MemberRoleUpdate
+    ].contains(&event.action_type) {
+        return Ok(())
+    }
 
     let moderator_id = event.user_id.ok_or(())?;
     let guild_id = event.guild_id.ok_or(())?;
@@ -24,35 +29,33 @@ pub async fn run(event: Box<GuildAuditLogEntryCreate>, discord_http: Arc<Client>
         return Err(())
     }
 
-    let change = event.changes.last().ok_or(())?;
-    let created_at = event.id.timestamp();
-
-    let duration = if let AuditLogChange::CommunicationDisabledUntil { old, new } = change {
-        if old.is_some() { return Err(()) };
-        if let Some(ends_on) = new {
-            Some(ends_on.as_secs() - created_at / 1000)
-        } else {
-            return Err(())
-        }
-    } else if event.action_type == AuditLogEventType::MemberUpdate {
-        return Ok(()) // its not a timeout
-    } else { None };
+    let (action_type, duration) = match get_case_action(&event, guild_config.moderation.mute_role) {
+        Some(action) => action,
+        None => return Ok(()) // member update not related to moderation
+    };
 
     let moderator = discord_http.user(moderator_id).await.map_err(|_| ())?.model().await.map_err(|_| ())?;
     if moderator.bot { return Ok(()) }
 
     let count = context.mongodb.get_next_case_index(guild_id).await.map_err(|_| ())?;
 
+    let created_at = DateTime::from_millis(event.id.timestamp());
+    let (expires_at, points) = guild_config.moderation.get_case_expiry(&action_type, created_at);
+
     let case = Case {
         moderator_id,
-        created_at: DateTime::from_millis(created_at),
+        created_at,
         guild_id,
         member_id: target_id.cast(),
         action: action_type,
-        reason: event.reason.to_owned(),
+        reason: event.reason.to_owned().filter(|reason| !reason.is_empty()),
         removed: false,
         duration,
-        index: count as u16
+        index: count,
+        related_cases: vec![],
+        expires_at,
+        points,
+        history: vec![]
     };
 
     let embed = case.to_embed(discord_http.clone()).await.map_err(|_| ())?;
@@ -67,4 +70,107 @@ pub async fn run(event: Box<GuildAuditLogEntryCreate>, discord_http: Arc<Client>
     ).await.map_err(|_| ())?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Maps the audit log entry to the case action type and the duration in seconds,
+/// returns `None` for entries which aren't moderation actions
+fn get_case_action(
+    event: &GuildAuditLogEntryCreate,
+    mute_role: Option<Id<RoleMarker>>
+) -> Option<(CaseActionType, Option<i64>)> {
+    let created_at = event.id.timestamp() / 1000;
+
+    match event.action_type {
+        AuditLogEventType::MemberKick => Some((CaseActionType::Kick, None)),
+        AuditLogEventType::MemberBanAdd => Some((CaseActionType::Ban, None)),
+        AuditLogEventType::MemberBanRemove => Some((CaseActionType::Unban, None)),
+        AuditLogEventType::MemberUpdate => event.changes.iter().find_map(|change| {
+            let (old, new) = match change {
+                AuditLogChange::CommunicationDisabledUntil { old, new } => (old, new),
+                _ => return None
+            };
+            // an expired timeout can stay as the old value
+            let was_timed_out = old.map(|ends_on| ends_on.as_secs() > created_at).unwrap_or(false);
+
+            match new {
+                Some(ends_on) if !was_timed_out => {
+                    Some((CaseActionType::Timeout, Some(ends_on.as_secs() - created_at)))
+                },
+                None if was_timed_out => Some((CaseActionType::Untimeout, None)),
+                _ => None
+            }
+        }),
+        AuditLogEventType::MemberRoleUpdate => {
+            let mute_role = mute_role?;
+            event.changes.iter().find_map(|change| match change {
+                AuditLogChange::RoleAdded { new, .. } if new.iter().any(|role| role.id == mute_role) => {
+                    Some((CaseActionType::Mute, None))
+                },
+                AuditLogChange::RoleRemoved { new, .. } if new.iter().any(|role| role.id == mute_role) => {
+                    Some((CaseActionType::Unmute, None))
+                },
+                _ => None
+            })
+        },
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::gateway::payload::incoming::GuildAuditLogEntryCreate;
+    use twilight_model::id::Id;
+    use crate::events::case::get_case_action;
+    use crate::models::case::CaseActionType;
+
+    // entries are created at 2023-11-14T22:13:20Z
+    fn parse(payload: &str) -> GuildAuditLogEntryCreate {
+        serde_json::from_str(payload).unwrap()
+    }
+
+    #[test]
+    fn test_bans() {
+        let ban = parse(r#"{"action_type":22,"changes":[],"guild_id":"1","id":"1174109840998400000",
+            "reason":"raid","target_id":"2","user_id":"3"}"#);
+        assert_eq!(get_case_action(&ban, None), Some((CaseActionType::Ban, None)));
+
+        let unban = parse(r#"{"action_type":23,"changes":[],"guild_id":"1","id":"1174109840998400000",
+            "target_id":"2","user_id":"3"}"#);
+        assert_eq!(get_case_action(&unban, None), Some((CaseActionType::Unban, None)));
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let timeout = parse(r#"{"action_type":24,"changes":[{"key":"communication_disabled_until",
+            "new_value":"2023-11-14T23:13:20.000000+00:00"}],"guild_id":"1","id":"1174109840998400000",
+            "target_id":"2","user_id":"3"}"#);
+        assert_eq!(get_case_action(&timeout, None), Some((CaseActionType::Timeout, Some(3600))));
+
+        let expired = parse(r#"{"action_type":24,"changes":[{"key":"communication_disabled_until",
+            "new_value":"2023-11-14T22:23:20.000000+00:00","old_value":"2023-11-13T10:00:00.000000+00:00"}],
+            "guild_id":"1","id":"1174109840998400000","target_id":"2","user_id":"3"}"#);
+        assert_eq!(get_case_action(&expired, None), Some((CaseActionType::Timeout, Some(600))));
+
+        let removed = parse(r#"{"action_type":24,"changes":[{"key":"communication_disabled_until",
+            "old_value":"2023-11-14T23:13:20.000000+00:00"}],"guild_id":"1","id":"1174109840998400000",
+            "target_id":"2","user_id":"3"}"#);
+        assert_eq!(get_case_action(&removed, None), Some((CaseActionType::Untimeout, None)));
+
+        let nickname = parse(r#"{"action_type":24,"changes":[{"key":"nick","new_value":"nick"}],
+            "guild_id":"1","id":"1174109840998400000","target_id":"2","user_id":"3"}"#);
+        assert_eq!(get_case_action(&nickname, None), None);
+    }
+
+    #[test]
+    fn test_mute_role() {
+        let mute = parse(r#"{"action_type":25,"changes":[{"key":"$add","new_value":[{"id":"4","name":"Muted"}]}],
+            "guild_id":"1","id":"1174109840998400000","reason":"spam","target_id":"2","user_id":"3"}"#);
+        assert_eq!(get_case_action(&mute, Some(Id::new(4))), Some((CaseActionType::Mute, None)));
+        assert_eq!(get_case_action(&mute, Some(Id::new(5))), None);
+        assert_eq!(get_case_action(&mute, None), None);
+
+        let unmute = parse(r#"{"action_type":25,"changes":[{"key":"$remove","new_value":[{"id":"4","name":"Muted"}]}],
+            "guild_id":"1","id":"1174109840998400000","target_id":"2","user_id":"3"}"#);
+        assert_eq!(get_case_action(&unmute, Some(Id::new(4))), Some((CaseActionType::Unmute, None)));
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use rand::Rng;
+use twilight_http::Client;
+use twilight_model::channel::Message;
+use twilight_model::channel::message::AllowedMentions;
+use crate::context::Context;
+
+pub async fn run(
+    message: Message,
+    discord_http: Arc<Client>,
+    context: Arc<Context>
+) -> Result<(), ()> {
+    if message.author.bot {
+        return Err(());
+    }
+
+    let guild_id = message.guild_id.ok_or(())?;
+    let config = context.mongodb.get_config(guild_id).await.map_err(|_| ())?;
+    if config.enabled.get("levels") != Some(&true) {
+        return Err(());
+    }
+
+    let author_id = message.author.id;
+    let levels = config.levels;
+
+    if levels.xp_timeout > 0 {
+        let can_gain_xp = context.redis.set_cooldown(
+            format!("levels_cooldown.{guild_id}.{author_id}"), levels.xp_timeout as usize
+        ).await.map_err(|_| ())?;
+        if !can_gain_xp { return Ok(()) }
+    }
+
+    let min = levels.xp_min.min(levels.xp_max);
+    let max = levels.xp_min.max(levels.xp_max);
+    let xp = rand::thread_rng().gen_range(min..=max);
+    if xp == 0 { return Ok(()) }
+
+    let total_xp = context.redis
+        .add_xp(format!("levels.{guild_id}"), author_id, xp as u32)
+        .await
+        .map_err(|_| ())?;
+
+    let (previous_level, _) = levels.curve.level_from_xp(total_xp.saturating_sub(xp as u64));
+    let (level, _) = levels.curve.level_from_xp(total_xp);
+    if level <= previous_level { return Ok(()) }
+
+    if !levels.rewards.is_empty() {
+        let roles = match &message.member {
+            Some(member) => member.roles.to_owned(),
+            None => discord_http.guild_member(guild_id, author_id)
+                .await.map_err(|_| ())?
+                .model().await.map_err(|_| ())?
+                .roles
+        };
+
+        if let Some(roles) = levels.roles_for_level(level, &roles) {
+            discord_http.update_guild_member(guild_id, author_id)
+                .roles(&roles).await.ok();
+        }
+    }
+
+    if let Some(announcement) = &levels.announcement {
+        let channel_id = announcement.channel_id.unwrap_or(message.channel_id);
+        // the template is set by admins, but only the member who leveled up should be pinged
+        let allowed_mentions = AllowedMentions { users: vec![author_id], ..Default::default() };
+        discord_http.create_message(channel_id)
+            .allowed_mentions(Some(&allowed_mentions))
+            .content(&announcement.format(author_id, level)).map_err(|_| ())?
+            .await.map_err(|_| ())?;
+    }
+
+    Ok(())
+}
@@ -8,6 +8,7 @@ use crate::utils::message::ConvertToMessage;
 pub mod automod;
 mod case;
 mod top;
+mod levels;
 mod cache;
 mod restore;
 mod setup;
@@ -20,14 +21,17 @@ pub async fn on_event(
 ) -> Result<(), ()> {
     match event {
         Event::MemberAdd(event) => {
-            self::restore::mutes::run(event, discord_http, context).await.ok();
+            // mutes overwrite member roles, so temporary roles are added after them
+            self::restore::mutes::run(event.to_owned(), discord_http.to_owned(), context.to_owned()).await.ok();
+            self::restore::temproles::run(event, discord_http, context).await.ok();
         }
         Event::BanRemove(event) => {
             self::restore::bans::run(event, &context.mongodb).await.ok();
         }
         Event::MessageCreate(event) => {
             let message = event.as_ref().0.to_owned();
-            self::automod::run(message.to_owned(), discord_http, context.to_owned(), TrigerEvent::MessageCreate).await.ok();
+            self::automod::run(message.to_owned(), discord_http.to_owned(), context.to_owned(), TrigerEvent::MessageCreate).await.ok();
+            self::levels::run(message.to_owned(), discord_http, context.to_owned()).await.ok();
             self::top::run(message, context).await.ok();
         }
         Event::MessageUpdate(event) => {
@@ -29,6 +29,38 @@ pub mod mutes {
     }
 }
 
+/// Gives temporary roles back after rejoin
+pub mod temproles {
+    use std::sync::Arc;
+    use futures_util::TryStreamExt;
+    use mongodb::bson::doc;
+    use twilight_model::gateway::payload::incoming::MemberAdd;
+    use crate::context::Context;
+    use crate::models::task::{Task, TaskAction};
+
+    pub async fn run(
+        member: Box<MemberAdd>,
+        discord_http: Arc<twilight_http::Client>,
+        context: Arc<Context>
+    ) -> Result<(), ()> {
+        let tasks: Vec<Task> = context.mongodb.tasks.find(doc! {
+            "action.RemoveRole.member_id": member.user.id.to_string(),
+            "guild_id": member.guild_id.to_string(),
+            "failed_at": null
+        }, None).await.map_err(|_| ())?.try_collect().await.map_err(|_| ())?;
+
+        for task in tasks {
+            if let TaskAction::RemoveRole { role_id, .. } = task.action {
+                if member.roles.contains(&role_id) { continue }
+                discord_http.add_guild_member_role(member.guild_id, member.user.id, role_id)
+                    .await.ok();
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Remove task after unban
 pub mod bans {
     use mongodb::bson::doc;
@@ -15,16 +15,9 @@ pub async fn run(
     let config = context.mongodb.get_config(guild_id).await.map_err(|_| ())?;
     let author_id = message.author.id;
 
-    if config.top.week {
+    for period in config.top.enabled_periods() {
         context.redis
-            .increase(format!("top_week.{guild_id}"), author_id, 1)
-            .await
-            .map_err(|_| ())?;
-    }
-
-    if config.top.day {
-        context.redis
-            .increase(format!("top_day.{guild_id}"), author_id, 1)
+            .increase(format!("top_{period}.{guild_id}"), author_id, 1)
             .await
             .map_err(|_| ())?;
     }
@@ -1,6 +1,10 @@
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use async_trait::async_trait;
 use futures_util::StreamExt;
-use tokio::sync::Mutex;
 use serde::{Deserialize, Serialize};
 use reqwest::Url;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
@@ -10,10 +14,108 @@ use crate::ok_or_return;
 const ALL_DOMAINS_ENDPOINT: &str = "https://phish.sinking.yachts/v2/all";
 const DOMAINS_FEED_ENDPOINT: &str = "wss://phish.sinking.yachts/feed";
 const GITHUB_REPO_URL: &str = "https://github.com/oceaann/custom";
+const SNAPSHOT_PATH: &str = "scam-domains.json";
+
+/// Time between downloading the full list, updates from the feed can be missed e.g. during reconnects
+const RESYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Listed domain and the source shown in logs, for every source name
+type SourceDomains = HashMap<String, HashMap<String, String>>;
+
+#[derive(Clone, Debug)]
+pub struct ScamLinksConfig {
+    /// Endpoint returning a JSON array of all domains
+    pub all_domains_url: String,
+    /// Websocket sending added and deleted domains
+    pub feed_url: String,
+    /// Endpoints returning plain-text lists with a domain per line
+    pub text_lists: Vec<String>,
+    /// Domains of all sources are saved there and loaded when the sources are unreachable
+    pub snapshot_path: Option<PathBuf>
+}
+
+impl ScamLinksConfig {
+    /// `SCAM_DOMAINS_URL` and `SCAM_DOMAINS_FEED_URL` replace the default phish.sinking.yachts endpoints,
+    /// `SCAM_DOMAINS_LISTS` is a comma-separated list of plain-text lists
+    /// and an empty `SCAM_DOMAINS_SNAPSHOT` disables the snapshot
+    pub fn from_env() -> Self {
+        let text_lists = std::env::var("SCAM_DOMAINS_LISTS").unwrap_or_default();
+        let snapshot_path = std::env::var("SCAM_DOMAINS_SNAPSHOT").unwrap_or_else(|_| SNAPSHOT_PATH.to_string());
+
+        Self {
+            all_domains_url: std::env::var("SCAM_DOMAINS_URL").unwrap_or_else(|_| ALL_DOMAINS_ENDPOINT.to_string()),
+            feed_url: std::env::var("SCAM_DOMAINS_FEED_URL").unwrap_or_else(|_| DOMAINS_FEED_ENDPOINT.to_string()),
+            text_lists: text_lists.split(',').map(str::trim).filter(|url| !url.is_empty()).map(String::from).collect(),
+            snapshot_path: (!snapshot_path.is_empty()).then(|| PathBuf::from(snapshot_path))
+        }
+    }
+}
+
+#[async_trait]
+pub trait DomainSource: Send + Sync {
+    /// Identifies domains of the source in the snapshot
+    fn name(&self) -> String;
+    /// Returns listed domains with the source shown in logs when they're matched
+    async fn fetch(&self) -> Result<Vec<(String, String)>, String>;
+}
+
+/// JSON array of domains, like the phish.sinking.yachts list
+pub struct JsonListSource {
+    url: String
+}
+
+#[async_trait]
+impl DomainSource for JsonListSource {
+    fn name(&self) -> String {
+        source_name(&self.url)
+    }
+
+    async fn fetch(&self) -> Result<Vec<(String, String)>, String> {
+        let domains = reqwest::get(&self.url).await.map_err(|err| format!("{err}"))?;
+        let domains: Vec<String> = serde_json::from_str(domains.text().await.map_err(|err| format!("{err}"))?.as_str()).map_err(|err| format!("{err}"))?;
+
+        let name = self.name();
+        Ok(domains.into_iter().map(|domain| (domain, name.to_owned())).collect())
+    }
+}
+
+/// Domain per line, `#` starts a comment and hosts file entries like `0.0.0.0 example.com` are supported
+pub struct TextListSource {
+    url: String
+}
+
+#[async_trait]
+impl DomainSource for TextListSource {
+    fn name(&self) -> String {
+        self.url.to_owned()
+    }
+
+    async fn fetch(&self) -> Result<Vec<(String, String)>, String> {
+        let response = reqwest::get(&self.url).await.map_err(|err| format!("{err}"))?;
+        if !response.status().is_success() { return Err(format!("Status {}", response.status())) }
+        let text = response.text().await.map_err(|err| format!("{err}"))?;
+
+        let source = source_name(&self.url);
+        Ok(parse_text_list(&text).map(|domain| (domain.to_string(), source.to_owned())).collect())
+    }
+}
+
+/// Domain which was found on the list
+#[derive(Debug, PartialEq)]
+pub struct ScamDomain {
+    pub domain: String,
+    pub source: String
+}
 
 #[derive(Clone)]
 pub struct ScamLinks {
-    discord_scam_domains: Arc<Mutex<Vec<String>>>
+    config: ScamLinksConfig,
+    sources: Arc<Vec<Box<dyn DomainSource>>>,
+    /// Name of the source updated by the feed
+    feed_source: String,
+    discord_scam_domains: Arc<RwLock<SourceDomains>>
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -24,72 +126,353 @@ struct UpdateMessage {
 }
 
 impl ScamLinks {
-    pub async fn new() -> Result<Self, String> {
+    /// Sources which cannot be downloaded are loaded from the snapshot, or start empty until the next resync
+    pub async fn new(config: ScamLinksConfig, extra_sources: Vec<Box<dyn DomainSource>>) -> Self {
+        let mut sources: Vec<Box<dyn DomainSource>> = vec![Box::new(JsonListSource { url: config.all_domains_url.to_owned() })];
+        sources.extend(config.text_lists.iter().map(|url| Box::new(TextListSource { url: url.to_owned() }) as Box<dyn DomainSource>));
+        sources.extend(extra_sources);
 
-        let domains = reqwest::get(ALL_DOMAINS_ENDPOINT).await.map_err(|err| format!("{err}"))?;
-        let domains: Vec<String> = serde_json::from_str(domains.text().await.map_err(|err| format!("{err}"))?.as_str()).map_err(|err| format!("{err}"))?;
+        let scam_links = Self {
+            feed_source: source_name(&config.all_domains_url),
+            sources: Arc::new(sources),
+            discord_scam_domains: Arc::new(RwLock::new(HashMap::new())),
+            config
+        };
 
-        Ok(Self {
-            discord_scam_domains: Arc::new(Mutex::new(domains))
-        })
+        let snapshot = scam_links.load_snapshot();
+        scam_links.sync(snapshot).await;
 
+        scam_links
     }
 
+    /// Listens for updates from the feed and reconnects when the connection drops,
+    /// the full list is downloaded again after every reconnect and periodically
     pub fn connect(&self) {
-        let discord_scam_domains = self.discord_scam_domains.clone();
+        let scam_links = self.clone();
         tokio::spawn(async move {
+            let mut delay = MIN_RECONNECT_DELAY;
+            let mut is_reconnect = false;
 
-            let mut request = Url::parse(DOMAINS_FEED_ENDPOINT)
-                .expect("Cannot parse url")
-                .into_client_request()
-                .expect("Cannot convert url into request");
+            loop {
+                if is_reconnect { scam_links.resync().await; }
+                is_reconnect = true;
+
+                match scam_links.listen().await {
+                    Ok(()) => {
+                        eprintln!("Connection to the scam domains feed closed");
+                        delay = MIN_RECONNECT_DELAY;
+                    },
+                    Err(error) => eprintln!("Cannot connect to the scam domains feed: {error}")
+                }
 
-            request.headers_mut().insert(
-                "X-Identity", HeaderValue::from_str(GITHUB_REPO_URL).expect("Cannot parse str to HeaderValue")
-            );
-            let (socket, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        });
 
-            let (_, read) = socket.split();
+        let scam_links = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RESYNC_INTERVAL);
+            // the first tick completes immediately, the list was just downloaded
+            interval.tick().await;
 
-            println!("Connected to phish.sinking.yachts");
+            loop {
+                interval.tick().await;
+                scam_links.resync().await;
+            }
+        });
+    }
 
-            read.for_each(|message| async {
-                let msg = ok_or_return!(message, Ok).into_data();
-                let test = ok_or_return!(String::from_utf8(msg), Ok);
-                let request = ok_or_return!(serde_json::from_str::<UpdateMessage>(test.as_str()), Ok);
-                if request.action == *"add" {
-                    for domain in request.domains {
-                        discord_scam_domains.lock().await.push(domain);
-                    }
+    /// Returns after the connection was closed
+    async fn listen(&self) -> Result<(), String> {
+        let mut request = Url::parse(&self.config.feed_url)
+            .map_err(|err| format!("{err}"))?
+            .into_client_request()
+            .map_err(|err| format!("{err}"))?;
+
+        request.headers_mut().insert(
+            "X-Identity", HeaderValue::from_str(GITHUB_REPO_URL).expect("Cannot parse str to HeaderValue")
+        );
+        let (socket, _) = tokio_tungstenite::connect_async(request).await.map_err(|err| format!("{err}"))?;
+
+        let (_, read) = socket.split();
+
+        println!("Connected to {}", self.config.feed_url);
+
+        read.for_each(|message| async {
+            let msg = ok_or_return!(message, Ok).into_data();
+            let text = ok_or_return!(String::from_utf8(msg), Ok);
+            let update = ok_or_return!(serde_json::from_str::<UpdateMessage>(text.as_str()), Ok);
+            self.apply(update);
+        }).await;
+
+        Ok(())
+    }
+
+    async fn resync(&self) {
+        let current = self.discord_scam_domains.read().unwrap().clone();
+        self.sync(current).await;
+    }
+
+    /// Downloads all sources, `previous` domains are kept for sources which cannot be downloaded
+    async fn sync(&self, mut previous: SourceDomains) {
+        let mut domains = HashMap::new();
+        let mut is_changed = false;
+
+        for source in self.sources.iter() {
+            let name = source.name();
+            match source.fetch().await {
+                Ok(entries) => {
+                    let entries = entries.into_iter().map(|(domain, source)| (normalize_domain(&domain), source));
+                    domains.insert(name, entries.collect());
+                    is_changed = true;
+                },
+                Err(error) => {
+                    eprintln!("Cannot download scam domains from {name}: {error}");
+                    if let Some(entries) = previous.remove(&name) { domains.insert(name, entries); }
                 }
-            }).await;
+            }
+        }
 
-        });
+        if is_changed { self.save_snapshot(&domains); }
+        *self.discord_scam_domains.write().unwrap() = domains;
+    }
+
+    fn load_snapshot(&self) -> SourceDomains {
+        let Some(path) = &self.config.snapshot_path else { return HashMap::new() };
+        let Ok(content) = std::fs::read(path) else { return HashMap::new() };
+
+        serde_json::from_slice(&content).unwrap_or_else(|error| {
+            eprintln!("Cannot load scam domains snapshot: {error}");
+            HashMap::new()
+        })
+    }
+
+    fn save_snapshot(&self, domains: &SourceDomains) {
+        let Some(path) = &self.config.snapshot_path else { return };
+        let content = ok_or_return!(serde_json::to_vec(domains), Ok);
+
+        if let Err(error) = std::fs::write(path, content) {
+            eprintln!("Cannot save scam domains snapshot: {error}");
+        }
     }
 
-    pub async fn contains(&self, domains: Vec<String>) -> bool {
-        let scam_domains = self.discord_scam_domains.lock().await;
-        for domain in domains {
-            if scam_domains.contains(&domain) { return true }
+    fn apply(&self, update: UpdateMessage) {
+        let mut scam_domains = self.discord_scam_domains.write().unwrap();
+        let domains = scam_domains.entry(self.feed_source.to_owned()).or_default();
+        match update.action.as_str() {
+            "add" => {
+                let source = &self.feed_source;
+                domains.extend(update.domains.iter().map(|domain| (normalize_domain(domain), source.to_owned())))
+            },
+            "delete" => {
+                for domain in update.domains {
+                    domains.remove(&normalize_domain(&domain));
+                }
+            },
+            _ => {}
         }
-        false
     }
+
+    /// Domains match also when any of their parent domains is listed
+    pub fn find(&self, domains: Vec<String>) -> Option<ScamDomain> {
+        let scam_domains = self.discord_scam_domains.read().unwrap();
+        domains.iter().find_map(|domain| {
+            parent_domains(&normalize_domain(domain)).find_map(|domain| {
+                scam_domains.values().find_map(|entries| entries.get(domain)).map(|source| ScamDomain {
+                    domain: domain.to_string(),
+                    source: source.to_owned()
+                })
+            })
+        })
+    }
+
+    pub fn contains(&self, domains: Vec<String>) -> bool {
+        self.find(domains).is_some()
+    }
+}
+
+/// Finds the domain or any of its parents in the list of a guild, these domains are matched only in that guild
+pub fn find_guild_domain(domains: &[String], guild_domains: &HashSet<String>) -> Option<ScamDomain> {
+    domains.iter().find_map(|domain| {
+        parent_domains(&normalize_domain(domain)).find(|domain| guild_domains.contains(*domain)).map(|domain| ScamDomain {
+            domain: domain.to_string(),
+            source: "guild list".to_string()
+        })
+    })
+}
+
+/// Host of the URL, so the source is recognizable in logs
+fn source_name(url: &str) -> String {
+    Url::parse(url).ok()
+        .and_then(|url| url.host_str().map(String::from))
+        .unwrap_or_else(|| url.to_string())
+}
+
+fn parse_text_list(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter_map(|line| line.split_whitespace().last())
+        .map(|domain| domain.trim_start_matches("*."))
+        .filter(|domain| domain.contains('.') && domain.parse::<IpAddr>().is_err())
+}
+
+fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_lowercase()
+}
+
+/// The domain and its parents, without the top-level domain, e.g. `a.b.com` and `b.com` for `a.b.com`
+fn parent_domains(domain: &str) -> impl Iterator<Item = &str> {
+    let labels = domain.split('.').count();
+    domain.match_indices('.')
+        .map(|(index, _)| &domain[index + 1..])
+        .take(labels.saturating_sub(2))
+        .chain(std::iter::once(domain).filter(|domain| !domain.is_empty()))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::links::ScamLinks;
+    use std::collections::HashSet;
+    use std::time::Duration;
+    use futures_util::SinkExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+    use async_trait::async_trait;
+    use crate::links::{DomainSource, ScamDomain, ScamLinks, ScamLinksConfig, find_guild_domain, parent_domains, parse_text_list};
 
     #[tokio::test]
     async fn test_contains_method() {
-        let scam_links = ScamLinks::new().await.unwrap();
+        let config = ScamLinksConfig { snapshot_path: None, ..ScamLinksConfig::from_env() };
+        let scam_links = ScamLinks::new(config, vec![]).await;
         assert_eq!(
-            scam_links.contains(vec!["moderating-verified-school.club".to_string()]).await,
+            scam_links.contains(vec!["moderating-verified-school.club".to_string()]),
             true
         );
         assert_eq!(
-            scam_links.contains(vec!["".to_string()]).await,
+            scam_links.contains(vec!["".to_string()]),
             false
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parent_domains() {
+        assert_eq!(parent_domains("a.b.com").collect::<Vec<&str>>(), vec!["b.com", "a.b.com"]);
+        assert_eq!(parent_domains("b.com").collect::<Vec<&str>>(), vec!["b.com"]);
+        assert_eq!(parent_domains("com").collect::<Vec<&str>>(), vec!["com"]);
+        assert_eq!(parent_domains("").count(), 0);
+    }
+
+    /// Serves the list of all domains over HTTP and sends `updates` to every feed connection
+    async fn start_mock_server(domains: &'static str, updates: Vec<&'static str>) -> ScamLinksConfig {
+        let http = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let feed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = ScamLinksConfig {
+            all_domains_url: format!("http://{}", http.local_addr().unwrap()),
+            feed_url: format!("ws://{}", feed.local_addr().unwrap()),
+            text_lists: vec![],
+            snapshot_path: None
+        };
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = http.accept().await {
+                // the request itself doesn't matter, every request gets the list
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{domains}", domains.len()
+                );
+                stream.write_all(response.as_bytes()).await.ok();
+            }
+        });
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = feed.accept().await {
+                let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+                for update in &updates {
+                    socket.send(Message::Text(update.to_string())).await.unwrap();
+                }
+                // the connection is closed to test reconnecting
+            }
+        });
+
+        config
+    }
+
+    #[tokio::test]
+    async fn test_feed_updates() {
+        let config = start_mock_server(r#"["scam.com", "old.com"]"#, vec![
+            r#"{"type": "add", "domains": ["new.com"]}"#,
+            r#"{"type": "delete", "domains": ["old.com"]}"#
+        ]).await;
+
+        let scam_links = ScamLinks::new(config, vec![]).await;
+        assert!(scam_links.contains(vec!["login.scam.com".to_string()]));
+        assert!(scam_links.contains(vec!["old.com".to_string()]));
+        assert!(!scam_links.contains(vec!["com".to_string(), "notscam.com".to_string()]));
+
+        scam_links.connect();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+
+        assert!(scam_links.contains(vec!["new.com".to_string()]));
+        assert!(!scam_links.contains(vec!["old.com".to_string()]));
+    }
+
+    /// Returns the same domains with the stub source until it's made unavailable
+    struct StubSource(Option<Vec<&'static str>>);
+
+    #[async_trait]
+    impl DomainSource for StubSource {
+        fn name(&self) -> String {
+            "stub".to_string()
+        }
+
+        async fn fetch(&self) -> Result<Vec<(String, String)>, String> {
+            let domains = self.0.as_ref().ok_or("Unavailable")?;
+            Ok(domains.iter().map(|domain| (domain.to_string(), "stub list".to_string())).collect())
+        }
+    }
+
+    #[test]
+    fn test_find_guild_domain() {
+        let guild_domains = HashSet::from(["guild.com".to_string()]);
+
+        assert_eq!(
+            find_guild_domain(&["A.Guild.com.".to_string()], &guild_domains),
+            Some(ScamDomain { domain: "guild.com".to_string(), source: "guild list".to_string() })
+        );
+        assert_eq!(find_guild_domain(&["notguild.com".to_string()], &guild_domains), None);
+        assert_eq!(find_guild_domain(&["guild.com".to_string()], &HashSet::new()), None);
+    }
+
+    #[test]
+    fn test_parse_text_list() {
+        let list = "# comment\nscam.com\n\n0.0.0.0 hosts.com # blocked\n*.wildcard.com\n127.0.0.1\nlocalhost";
+        assert_eq!(parse_text_list(list).collect::<Vec<&str>>(), vec!["scam.com", "hosts.com", "wildcard.com"]);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_fallback() {
+        let snapshot_path = std::env::temp_dir().join(format!("scam-domains-{}.json", std::process::id()));
+        let mut config = start_mock_server(r#"["scam.com"]"#, vec![]).await;
+        config.snapshot_path = Some(snapshot_path.to_owned());
+
+        let scam_links = ScamLinks::new(config.to_owned(), vec![Box::new(StubSource(Some(vec!["Guild.com"])))]).await;
+        assert_eq!(
+            scam_links.find(vec!["a.guild.com".to_string()]),
+            Some(ScamDomain { domain: "guild.com".to_string(), source: "stub list".to_string() })
+        );
+        assert_eq!(
+            scam_links.find(vec!["scam.com".to_string()]),
+            Some(ScamDomain { domain: "scam.com".to_string(), source: "127.0.0.1".to_string() })
+        );
+
+        // all sources are unreachable, so domains are loaded from the snapshot
+        let config = ScamLinksConfig { all_domains_url: "http://127.0.0.1:1".to_string(), ..config };
+        let scam_links = ScamLinks::new(config, vec![Box::new(StubSource(None))]).await;
+        std::fs::remove_file(snapshot_path).ok();
+
+        assert!(scam_links.contains(vec!["guild.com".to_string()]));
+        assert!(scam_links.contains(vec!["scam.com".to_string()]));
+    }
+}
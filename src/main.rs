@@ -9,6 +9,8 @@ all_macro!(
     mod events;
     mod links;
     mod bucket;
+    mod duplicates;
+    mod resolver;
 );
 
 mod context;
@@ -45,6 +47,7 @@ async fn main() {
         {
             threads.push(tasks::run(
                 context.mongodb.to_owned(),
+                context.redis.to_owned(),
                 discord_clients.to_owned(),
                 main_http.to_owned()
             ));
@@ -10,6 +10,7 @@ use twilight_model::id::marker::{GuildMarker, UserMarker};
 use twilight_model::util::datetime::TimestampParseError;
 use twilight_model::util::Timestamp;
 use crate::utils::avatars::{DEFAULT_AVATAR, get_avatar_url, get_guild_icon_url};
+use crate::utils::embeds::EmbedBuilder;
 use crate::utils::errors::Error;
 use serde::{Serialize, Deserialize};
 use crate::database::redis::RedisConnection;
@@ -24,7 +25,67 @@ pub struct Case {
     pub reason: Option<String>,
     pub removed: bool,
     pub duration: Option<i64>,
-    pub index: u16
+    pub index: u64,
+    /// Numbers of cases this one refers to, e.g. the ban lifted by an unban
+    #[serde(default)]
+    pub related_cases: Vec<u64>,
+    /// The case stops counting to active totals after this time
+    #[serde(default)]
+    pub expires_at: Option<DateTime>,
+    #[serde(default = "default_points")]
+    pub points: u32,
+    #[serde(default)]
+    pub history: Vec<CaseChange>
+}
+
+/// Change made to the case after it was created
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CaseChange {
+    pub actor_id: Id<UserMarker>,
+    pub changed_at: DateTime,
+    /// Name of the changed field
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>
+}
+
+impl CaseChange {
+    pub fn new(actor_id: Id<UserMarker>, field: &str, old_value: Option<String>, new_value: Option<String>) -> Self {
+        Self {
+            actor_id,
+            changed_at: DateTime::now(),
+            field: field.to_string(),
+            old_value,
+            new_value
+        }
+    }
+
+    /// Long values like reasons are shortened, so many changes fit into one embed
+    pub fn describe(&self) -> String {
+        format!(
+            "<t:{}:f> <@{}> changed **{}** from `{}` to `{}`",
+            self.changed_at.timestamp_millis() / 1000,
+            self.actor_id,
+            self.field,
+            self.old_value.as_deref().map(shorten_value).unwrap_or_else(|| "None".to_string()),
+            self.new_value.as_deref().map(shorten_value).unwrap_or_else(|| "None".to_string())
+        )
+    }
+}
+
+/// Maximum number of characters of a value quoted in the description of a change
+const MAX_CHANGE_VALUE_LENGTH: usize = 100;
+
+fn shorten_value(value: &str) -> String {
+    if value.chars().count() <= MAX_CHANGE_VALUE_LENGTH { return value.to_string() }
+    let shortened = value.chars().take(MAX_CHANGE_VALUE_LENGTH - 1).collect::<String>();
+    format!("{shortened}…")
+}
+
+pub const DEFAULT_CASE_POINTS: u32 = 1;
+
+fn default_points() -> u32 {
+    DEFAULT_CASE_POINTS
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -37,6 +98,7 @@ pub enum CaseActionType {
     Unban,
     Kick,
     Timeout,
+    Untimeout,
     Unknown(u8)
 }
 
@@ -50,6 +112,7 @@ impl From<u8> for CaseActionType {
             5 => CaseActionType::Unban,
             6 => CaseActionType::Kick,
             7 => CaseActionType::Timeout,
+            8 => CaseActionType::Untimeout,
             _ => CaseActionType::Unknown(action_type)
         }
     }
@@ -65,11 +128,39 @@ impl From<CaseActionType> for u8 {
             CaseActionType::Unban => 5,
             CaseActionType::Kick => 6,
             CaseActionType::Timeout => 7,
+            CaseActionType::Untimeout => 8,
             CaseActionType::Unknown(action_type) => action_type
         }
     }
 }
 
+impl CaseActionType {
+    /// Whether the action lifts an earlier punishment
+    pub fn is_reverse(&self) -> bool {
+        matches!(self, CaseActionType::Unmute | CaseActionType::Unban | CaseActionType::Untimeout)
+    }
+
+    /// Points of the case when no case rule sets them, reverse cases aren't counted as punishments
+    pub fn default_points(&self) -> u32 {
+        if self.is_reverse() { 0 } else { DEFAULT_CASE_POINTS }
+    }
+
+    /// Plural name used in case summaries
+    pub fn plural_name(&self) -> &'static str {
+        match self {
+            CaseActionType::Warn => "Warns",
+            CaseActionType::Mute => "Mutes",
+            CaseActionType::Unmute => "Unmutes",
+            CaseActionType::Ban => "Bans",
+            CaseActionType::Unban => "Unbans",
+            CaseActionType::Kick => "Kicks",
+            CaseActionType::Timeout => "Timeouts",
+            CaseActionType::Untimeout => "Untimeouts",
+            CaseActionType::Unknown(_) => "Other"
+        }
+    }
+}
+
 impl Case {
     pub async fn to_dm_embed(&self, redis: &RedisConnection) -> Result<Embed, Error> {
         let guild = redis.get_guild(self.guild_id).await.map_err(Error::from)?;
@@ -125,6 +216,14 @@ impl Case {
             self.reason.to_owned().unwrap_or_else(|| "None".to_string())
         );
 
+        if !self.related_cases.is_empty() {
+            let related = self.related_cases.iter()
+                .map(|index| format!("#{index}"))
+                .collect::<Vec<String>>()
+                .join(", ");
+            description.push_str(&format!("\n**Related cases:** {related}"));
+        }
+
         if moderator {
             description.insert_str(0, &*format!("**Moderator:** <@{}>**\n", self.moderator_id))
         }
@@ -155,6 +254,19 @@ impl Case {
         })
     }
 
+    /// Embed posted to the logs channel after the case was changed
+    pub fn to_change_embed(&self, change: &CaseChange) -> Embed {
+        EmbedBuilder::new()
+            .title(format!("Case #{} changed", self.index))
+            .description(format!("**Member:** <@{}>\n{}", self.member_id, change.describe()))
+            .to_embed()
+    }
+
+    /// Returns true when the case wasn't removed and didn't expire
+    pub fn is_active(&self, now: DateTime) -> bool {
+        !self.removed && self.expires_at.map(|expires_at| expires_at > now).unwrap_or(true)
+    }
+
     pub fn to_field(&self) -> EmbedField {
         let reason = self.reason.to_owned().unwrap_or_else(|| "None".to_string());
         EmbedField {
@@ -163,4 +275,19 @@ impl Case {
             value: reason
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use twilight_model::id::Id;
+    use crate::models::case::CaseChange;
+
+    #[test]
+    fn test_describe_change() {
+        let change = CaseChange::new(Id::new(1), "reason", Some("a".repeat(512)), None);
+        let description = change.describe();
+
+        assert!(description.contains(&format!("`{}…`", "a".repeat(99))));
+        assert!(description.ends_with("to `None`"));
+        assert!(description.chars().count() < 200);
+    }
+}
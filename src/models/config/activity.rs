@@ -1,15 +1,213 @@
 use serde::{Serialize, Deserialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use twilight_model::id::Id;
+use twilight_model::id::marker::{ChannelMarker, RoleMarker, UserMarker};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Levels {
     pub xp_timeout: u16,
     pub xp_min: u8,
-    pub xp_max: u8
+    pub xp_max: u8,
+    #[serde(default)]
+    pub curve: LevelCurve,
+    #[serde(default)]
+    pub rewards: Vec<LevelReward>,
+    #[serde(default)]
+    pub rewards_mode: RewardsMode,
+    pub announcement: Option<LevelUpAnnouncement>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelReward {
+    pub level: u32,
+    pub role_id: Id<RoleMarker>
+}
+
+#[derive(Serialize_repr, Deserialize_repr, Debug, Clone, PartialEq, Default)]
+#[repr(u8)]
+pub enum RewardsMode {
+    /// Member keeps roles from all reached levels
+    #[default]
+    Stack = 1,
+    /// Member keeps only the role from the highest reached level
+    Replace = 2
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelUpAnnouncement {
+    /// When not set, the message is sent to the channel where the member leveled up
+    pub channel_id: Option<Id<ChannelMarker>>,
+    /// Message content, `{user}` and `{level}` are replaced with the member mention and the new level
+    pub template: String
+}
+
+impl LevelUpAnnouncement {
+    pub fn format(&self, user_id: Id<UserMarker>, level: u32) -> String {
+        self.template
+            .replace("{user}", &format!("<@{user_id}>"))
+            .replace("{level}", &level.to_string())
+    }
+}
+
+impl Levels {
+    /// Returns member roles with rewards for the level applied or `None` when nothing has to change
+    pub fn roles_for_level(&self, level: u32, roles: &[Id<RoleMarker>]) -> Option<Vec<Id<RoleMarker>>> {
+        let mut reached = self.rewards.iter()
+            .filter(|reward| reward.level <= level)
+            .collect::<Vec<&LevelReward>>();
+        reached.sort_by_key(|reward| reward.level);
+
+        let granted = match self.rewards_mode {
+            RewardsMode::Stack => reached.iter().map(|reward| reward.role_id).collect(),
+            RewardsMode::Replace => reached.last().map(|reward| vec![reward.role_id]).unwrap_or_default()
+        };
+
+        let mut result = roles.to_vec();
+        if self.rewards_mode == RewardsMode::Replace {
+            result.retain(|role| !self.rewards.iter().any(|reward| &reward.role_id == role));
+        }
+
+        for role in granted {
+            if !result.contains(&role) { result.push(role) }
+        }
+
+        let changed = result.len() != roles.len() || result.iter().any(|role| !roles.contains(role));
+        changed.then_some(result)
+    }
+}
+
+/// Levels above it aren't counted, it also keeps `LevelCurve::xp_to_reach_level` from overflowing
+const MAX_LEVEL: u32 = 1_000_000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum LevelCurve {
+    /// Every level requires the same amount of XP
+    Linear { step: u32 },
+    /// Level `n` requires `a * n^2 + b * n + c` XP to advance to the next one
+    Quadratic { a: u32, b: u32, c: u32 }
+}
+
+impl Default for LevelCurve {
+    fn default() -> Self {
+        Self::Quadratic { a: 5, b: 50, c: 100 }
+    }
+}
+
+impl LevelCurve {
+    /// Amount of XP required to advance from `level` to the next one
+    pub fn xp_to_next_level(&self, level: u32) -> u64 {
+        let level = level as u64;
+        match self {
+            LevelCurve::Linear { step } => *step as u64,
+            LevelCurve::Quadratic { a, b, c } => {
+                (*a as u64) * level * level + (*b as u64) * level + (*c as u64)
+            }
+        }
+    }
+
+    /// Total amount of XP required to reach `level` from level 0
+    fn xp_to_reach_level(&self, level: u32) -> u128 {
+        let level = level as u128;
+        match self {
+            LevelCurve::Linear { step } => (*step as u128) * level,
+            LevelCurve::Quadratic { a, b, c } => {
+                // sums of n^2 and n for n in 0..level
+                let squares = level.saturating_sub(1) * level * (2 * level).saturating_sub(1) / 6;
+                let linear = level.saturating_sub(1) * level / 2;
+                (*a as u128) * squares + (*b as u128) * linear + (*c as u128) * level
+            }
+        }
+    }
+
+    /// Returns the level and the XP gathered since reaching it
+    pub fn level_from_xp(&self, xp: u64) -> (u32, u64) {
+        if self.xp_to_next_level(0) == 0 { return (0, xp) }
+
+        // the required XP grows with the level, so the highest reached level can be found with a binary search
+        let (mut low, mut high) = (0, MAX_LEVEL);
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            if self.xp_to_reach_level(middle) <= xp as u128 {
+                low = middle;
+            } else { high = middle - 1 }
+        }
+
+        (low, xp - self.xp_to_reach_level(low) as u64)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Top {
     pub week: bool,
     pub day: bool,
+    #[serde(default)]
+    pub month: bool,
+    #[serde(default)]
+    pub alltime: bool,
     pub webhook_url: String
-}
\ No newline at end of file
+}
+
+impl Top {
+    /// Returns names of periods which are counted
+    pub fn enabled_periods(&self) -> Vec<&'static str> {
+        [(self.day, "day"), (self.week, "week"), (self.month, "month"), (self.alltime, "alltime")]
+            .into_iter()
+            .filter_map(|(enabled, period)| enabled.then_some(period))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::id::Id;
+    use crate::models::config::activity::{LevelCurve, LevelReward, Levels, RewardsMode};
+
+    #[test]
+    fn test_level_from_xp() {
+        let curve = LevelCurve::default();
+        assert_eq!(curve.level_from_xp(0), (0, 0));
+        assert_eq!(curve.level_from_xp(99), (0, 99));
+        assert_eq!(curve.level_from_xp(100), (1, 0));
+        assert_eq!(curve.level_from_xp(254), (1, 154));
+        assert_eq!(curve.level_from_xp(255), (2, 0));
+
+        let curve = LevelCurve::Linear { step: 10 };
+        assert_eq!(curve.level_from_xp(35), (3, 5));
+
+        let curve = LevelCurve::Linear { step: 0 };
+        assert_eq!(curve.level_from_xp(35), (0, 35));
+
+        let curve = LevelCurve::Linear { step: 1 };
+        assert_eq!(curve.level_from_xp(u64::MAX), (1_000_000, u64::MAX - 1_000_000));
+
+        let curve = LevelCurve::Quadratic { a: 1, b: 0, c: 1 };
+        assert_eq!(curve.level_from_xp(u64::MAX).0, 1_000_000);
+        assert_eq!(curve.level_from_xp(9), (3, 1));
+    }
+
+    #[test]
+    fn test_roles_for_level() {
+        let mut levels = Levels {
+            xp_timeout: 0,
+            xp_min: 0,
+            xp_max: 0,
+            curve: LevelCurve::default(),
+            rewards: vec![
+                LevelReward { level: 5, role_id: Id::new(5) },
+                LevelReward { level: 10, role_id: Id::new(10) }
+            ],
+            rewards_mode: RewardsMode::Stack,
+            announcement: None
+        };
+
+        assert_eq!(levels.roles_for_level(1, &[Id::new(1)]), None);
+        assert_eq!(levels.roles_for_level(5, &[Id::new(1)]), Some(vec![Id::new(1), Id::new(5)]));
+        assert_eq!(levels.roles_for_level(10, &[Id::new(5)]), Some(vec![Id::new(5), Id::new(10)]));
+        assert_eq!(levels.roles_for_level(10, &[Id::new(10), Id::new(5)]), None);
+
+        levels.rewards_mode = RewardsMode::Replace;
+        assert_eq!(levels.roles_for_level(10, &[Id::new(1), Id::new(5)]), Some(vec![Id::new(1), Id::new(10)]));
+        assert_eq!(levels.roles_for_level(10, &[Id::new(10)]), None);
+    }
+}
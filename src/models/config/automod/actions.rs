@@ -16,7 +16,9 @@ pub enum Action {
     SendLogs,
     Timeout(Timeout),
     Kick,
-    Ban
+    Ban,
+    /// Creates a warn case without any other punishment
+    Warn
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -25,7 +27,7 @@ pub enum IncreaseBucketAmount {
     Stickers,
     Attachments,
     Mentions,
-    Static(u8)
+    Static(u32)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -41,8 +43,8 @@ pub struct IncreaseBucket {
 pub struct BucketAction {
     pub actions: Vec<ActionMetadata>,
     pub reason: String,
-    /// Minimal value required to run action
-    pub limit: u8
+    /// Actions are run once the count goes above this value
+    pub limit: u32
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
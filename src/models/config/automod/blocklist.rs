@@ -0,0 +1,160 @@
+use aho_corasick::AhoCorasick;
+use regex::RegexSet;
+use crate::models::config::automod::checks::Blocklist;
+use crate::utils::normalize::normalize;
+
+/// Blocklist compiled into a single Aho-Corasick automaton for words and substrings
+/// and a `RegexSet` for wildcards.
+/// Repeated letters are matched by their runs, a letter repeated in the term has to be repeated in the text
+/// while a letter repeated in the text also matches a single one of the term
+#[derive(Debug)]
+pub struct BlocklistMatcher {
+    automaton: Option<AhoCorasick>,
+    /// Configured term, whether it has to be a whole word and lengths of the runs of the pattern,
+    /// for every pattern of the automaton
+    terms: Vec<(String, bool, Vec<u8>)>,
+    wildcards: Option<RegexSet>,
+    wildcard_terms: Vec<String>
+}
+
+impl BlocklistMatcher {
+    pub fn new(config: &Blocklist) -> Result<Self, String> {
+        let mut patterns = vec![];
+        let mut terms = vec![];
+
+        let entries = config.words.iter().map(|word| (word, true))
+            .chain(config.substrings.iter().map(|substring| (substring, false)));
+
+        for (term, whole_word) in entries {
+            let (pattern, runs) = collapse_runs(&normalize(term));
+            // an empty pattern would match every message
+            if pattern.is_empty() { continue }
+            patterns.push(pattern);
+            terms.push((term.to_owned(), whole_word, runs));
+        }
+
+        let automaton = if patterns.is_empty() { None } else {
+            Some(AhoCorasick::new(&patterns).map_err(|error| format!("Cannot build blocklist: {error}"))?)
+        };
+
+        let mut wildcard_patterns = vec![];
+        let mut wildcard_terms = vec![];
+        for term in &config.wildcards {
+            let parts = term.split('*').map(|part| runs_pattern(&normalize(part))).collect::<Vec<String>>();
+            if parts.iter().all(String::is_empty) { continue }
+
+            wildcard_patterns.push(format!(r"\b{}\b", parts.join(r"\w*")));
+            wildcard_terms.push(term.to_owned());
+        }
+
+        let wildcards = if wildcard_patterns.is_empty() { None } else {
+            Some(RegexSet::new(&wildcard_patterns).map_err(|error| format!("Cannot build blocklist wildcards: {error}"))?)
+        };
+
+        Ok(Self { automaton, terms, wildcards, wildcard_terms })
+    }
+
+    /// Returns the configured term found in the normalized text
+    pub fn find(&self, normalized: &str) -> Option<&str> {
+        if let Some(automaton) = &self.automaton {
+            let (collapsed, runs) = collapse_runs(normalized);
+            let char_starts = collapsed.char_indices().map(|(index, _)| index).collect::<Vec<usize>>();
+
+            // overlapping matches are needed, the leftmost one may not be a whole word
+            for found in automaton.find_overlapping_iter(&collapsed) {
+                let (term, whole_word, pattern_runs) = &self.terms[found.pattern().as_usize()];
+                let Ok(first) = char_starts.binary_search(&found.start()) else { continue };
+
+                let runs_match = pattern_runs.iter().zip(&runs[first..]).all(|(pattern, text)| text >= pattern);
+                if runs_match && (!whole_word || is_whole_word(&collapsed, found.start(), found.end())) {
+                    return Some(term)
+                }
+            }
+        }
+
+        let wildcards = self.wildcards.as_ref()?;
+        let index = wildcards.matches(normalized).into_iter().next()?;
+        self.wildcard_terms.get(index).map(String::as_str)
+    }
+}
+
+/// Returns the text with every run of the same character shortened to one and lengths of the runs
+fn collapse_runs(text: &str) -> (String, Vec<u8>) {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut runs: Vec<u8> = vec![];
+    let mut last = None;
+
+    for char in text.chars() {
+        if last == Some(char) {
+            if let Some(run) = runs.last_mut() { *run = run.saturating_add(1) }
+            continue
+        }
+
+        last = Some(char);
+        collapsed.push(char);
+        runs.push(1);
+    }
+
+    (collapsed, runs)
+}
+
+/// Regex matching the text with the same runs as `collapse_runs` does, e.g. `ass` becomes `a+ss+`
+fn runs_pattern(text: &str) -> String {
+    let (collapsed, runs) = collapse_runs(text);
+    collapsed.chars().zip(runs).map(|(char, run)| {
+        format!("{}+", regex::escape(&char.to_string().repeat(run as usize)))
+    }).collect()
+}
+
+fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    !before.map(char::is_alphanumeric).unwrap_or(false) && !after.map(char::is_alphanumeric).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::config::automod::blocklist::BlocklistMatcher;
+    use crate::models::config::automod::checks::Blocklist;
+    use crate::utils::normalize::normalize;
+
+    #[test]
+    fn test_blocklist() {
+        let matcher = BlocklistMatcher::new(&Blocklist {
+            words: vec!["scam".to_string(), "".to_string()],
+            substrings: vec!["freenitro".to_string()],
+            wildcards: vec!["hack*".to_string()]
+        }).unwrap();
+
+        let find = |text: &str| matcher.find(&normalize(text)).map(str::to_string);
+
+        assert_eq!(find("this is a sc4m!"), Some("scam".to_string()));
+        assert_eq!(find("ѕсаааm"), Some("scam".to_string()));
+        assert_eq!(find("scammer"), None);
+        assert_eq!(find("get FREE\u{200B}NITRO here"), Some("freenitro".to_string()));
+        assert_eq!(find("free nitro"), None);
+        assert_eq!(find("h4ck3rs everywhere"), Some("hack*".to_string()));
+        assert_eq!(find("shacks"), None);
+        assert_eq!(find("nothing here"), None);
+        assert_eq!(find("haaackers"), Some("hack*".to_string()));
+    }
+
+    #[test]
+    fn test_blocklist_repeated_letters() {
+        let matcher = BlocklistMatcher::new(&Blocklist {
+            words: vec!["ass".to_string()],
+            substrings: vec!["boob".to_string()],
+            wildcards: vec!["kill*".to_string()]
+        }).unwrap();
+
+        let find = |text: &str| matcher.find(&normalize(text)).map(str::to_string);
+
+        assert_eq!(find("as if"), None);
+        assert_eq!(find("kiss my ass"), Some("ass".to_string()));
+        assert_eq!(find("kiss my aaassss"), Some("ass".to_string()));
+        assert_eq!(find("bob"), None);
+        assert_eq!(find("bo0oob"), Some("boob".to_string()));
+        assert_eq!(find("kiln"), None);
+        assert_eq!(find("kiiilled"), Some("kill*".to_string()));
+    }
+}
@@ -7,7 +7,13 @@ pub enum Check {
     TextLines(TextLines),
     CapsLock(CapsLock),
     Invites(Invites),
-    Regex(Regex)
+    Regex(Regex),
+    Blocklist(Blocklist),
+    Mentions(Mentions),
+    Emojis(Emojis),
+    Zalgo(Zalgo),
+    Duplicates(Duplicates),
+    Links(Links)
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -34,4 +40,65 @@ pub struct TextLines {
 pub struct Regex {
     pub is_matching: bool,
     pub regex: String
-}
\ No newline at end of file
+}
+
+/// Terms are matched after normalizing both the terms and the message content
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Blocklist {
+    /// Matched only as whole words
+    #[serde(default)]
+    pub words: Vec<String>,
+    /// Matched anywhere, also inside other words
+    #[serde(default)]
+    pub substrings: Vec<String>,
+    /// Whole words where `*` matches any number of letters, e.g. `bad*`
+    #[serde(default)]
+    pub wildcards: Vec<String>
+}
+
+/// Matches when any of the set limits is reached
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Mentions {
+    /// Number of mentioned roles
+    pub roles: Option<u16>,
+    /// Number of `@everyone` and `@here` mentions, counted also when the author cannot use them
+    pub everyone: Option<u16>
+}
+
+/// Custom and Unicode emojis
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Emojis {
+    pub min: Option<u16>,
+    pub max: Option<u16>
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+// min in % of combining characters to other characters
+pub struct Zalgo {
+    pub min: u8
+}
+
+/// Same content posted `count` times within `duration` seconds
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Duplicates {
+    pub count: u16,
+    pub duration: u16,
+    /// Counts posts in the same channel once, so only content posted across channels matches
+    #[serde(default)]
+    pub distinct_channels: bool
+}
+
+/// Domains match also all of their subdomains, when a link matches both lists the more specific domain is used
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Links {
+    #[serde(default)]
+    pub allowed: Vec<String>,
+    #[serde(default)]
+    pub denied: Vec<String>,
+    /// Matches all links except ones with allowed domains
+    #[serde(default)]
+    pub allowlist_only: bool,
+    /// Links of known URL shorteners are checked by the domain they redirect to
+    #[serde(default)]
+    pub resolve_shorteners: bool
+}
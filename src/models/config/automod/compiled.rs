@@ -0,0 +1,179 @@
+use std::collections::{HashMap, HashSet};
+use regex::{Regex, RegexSet, SetMatches};
+use crate::models::config::automod::AutoModeration;
+use crate::models::config::automod::blocklist::BlocklistMatcher;
+use crate::models::config::automod::checks::Check;
+use crate::utils::normalize::normalize;
+
+/// With more regex checks than this, all of them are matched in a single pass with a `RegexSet`
+const REGEX_SET_THRESHOLD: usize = 3;
+
+/// Regexes of all `Check::Regex` checks and matchers of all `Check::Blocklist` checks, compiled once per config
+#[derive(Debug)]
+pub struct CompiledAutoModeration {
+    regexes: Vec<Regex>,
+    set: Option<RegexSet>,
+    /// Index in `regexes` for the check at (rule index, check index)
+    positions: HashMap<(usize, usize), usize>,
+    blocklists: HashMap<(usize, usize), BlocklistMatcher>,
+    /// Rules with at least one invalid pattern, these are skipped
+    invalid_rules: HashSet<usize>,
+    errors: Vec<String>,
+    /// Longest window of `Check::Duplicates` checks, messages are remembered only when it's set
+    duplicates_duration: Option<u16>,
+    /// Lowercase scam domains listed by the guild, without trailing dots
+    scam_domains: HashSet<String>
+}
+
+impl CompiledAutoModeration {
+    pub fn compile(config: &AutoModeration) -> Self {
+        let mut regexes = vec![];
+        let mut positions = HashMap::new();
+        let mut blocklists = HashMap::new();
+        let mut invalid_rules = HashSet::new();
+        let mut errors = vec![];
+        let mut duplicates_duration = None;
+
+        for (rule_index, rule) in config.rules.iter().enumerate() {
+            for (check_index, check) in rule.checks.iter().enumerate() {
+                let result = match check {
+                    Check::Regex(config) => Regex::new(&config.regex).map(|regex| {
+                        positions.insert((rule_index, check_index), regexes.len());
+                        regexes.push(regex);
+                    }).map_err(|error| error.to_string()),
+                    Check::Blocklist(config) => BlocklistMatcher::new(config).map(|matcher| {
+                        blocklists.insert((rule_index, check_index), matcher);
+                    }),
+                    Check::Duplicates(config) => {
+                        duplicates_duration = duplicates_duration.max(Some(config.duration));
+                        continue
+                    },
+                    _ => continue
+                };
+
+                if let Err(error) = result {
+                    invalid_rules.insert(rule_index);
+                    errors.push(format!("Invalid check in rule `{}`: {error}", rule.name));
+                }
+            }
+        }
+
+        // the set can still exceed size limits, then regexes are matched one by one
+        let set = (regexes.len() > REGEX_SET_THRESHOLD)
+            .then(|| RegexSet::new(regexes.iter().map(Regex::as_str)).ok())
+            .flatten();
+
+        let scam_domains = config.scam_domains.iter()
+            .map(|domain| domain.trim().trim_end_matches('.').to_lowercase())
+            .collect();
+
+        Self { regexes, set, positions, blocklists, invalid_rules, errors, duplicates_duration, scam_domains }
+    }
+
+    /// Errors of patterns which couldn't be compiled
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    pub fn duplicates_duration(&self) -> Option<u16> {
+        self.duplicates_duration
+    }
+
+    pub fn is_rule_valid(&self, rule_index: usize) -> bool {
+        !self.invalid_rules.contains(&rule_index)
+    }
+
+    pub fn matcher<'a>(&'a self, content: &'a str) -> MessageMatcher<'a> {
+        MessageMatcher {
+            set_matches: self.set.as_ref().map(|set| set.matches(content)),
+            // the content is normalized once for all blocklists
+            normalized: (!self.blocklists.is_empty()).then(|| normalize(content)),
+            compiled: self,
+            content
+        }
+    }
+}
+
+/// Matches compiled checks of all rules against a single message
+pub struct MessageMatcher<'a> {
+    compiled: &'a CompiledAutoModeration,
+    content: &'a str,
+    set_matches: Option<SetMatches>,
+    normalized: Option<String>
+}
+
+impl MessageMatcher<'_> {
+    /// Returns `None` when the check at the position isn't a compiled regex check
+    pub fn is_match(&self, rule_index: usize, check_index: usize) -> Option<bool> {
+        let index = *self.compiled.positions.get(&(rule_index, check_index))?;
+        Some(match &self.set_matches {
+            Some(matches) => matches.matched(index),
+            None => self.compiled.regexes.get(index)?.is_match(self.content)
+        })
+    }
+
+    pub fn scam_domains(&self) -> &HashSet<String> {
+        &self.compiled.scam_domains
+    }
+
+    /// Returns the blocklisted term found by the blocklist check at the position
+    pub fn find_blocklisted(&self, rule_index: usize, check_index: usize) -> Option<&str> {
+        let blocklist = self.compiled.blocklists.get(&(rule_index, check_index))?;
+        blocklist.find(self.normalized.as_ref()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::models::config::automod::{AutoModeration, AutoModerationRule};
+    use crate::models::config::automod::checks::{CapsLock, Check, Regex};
+    use crate::models::config::automod::compiled::CompiledAutoModeration;
+
+    fn create_config(patterns: &[&str]) -> AutoModeration {
+        AutoModeration {
+            rules: patterns.iter().map(|pattern| AutoModerationRule {
+                basic_type: None,
+                check_on_edit: false,
+                filters: vec![],
+                checks: vec![Check::CapsLock(CapsLock { min: None, max: None }), Check::Regex(Regex {
+                    is_matching: true,
+                    regex: pattern.to_string()
+                })],
+                actions: vec![],
+                ignore: None,
+                reason: "".to_string(),
+                name: pattern.to_string()
+            }).collect(),
+            bucket_actions: HashMap::new(),
+            logs_channel: None,
+            ignore: None,
+            scam_domains: vec![],
+            compiled: None
+        }
+    }
+
+    #[test]
+    fn test_compile() {
+        let compiled = CompiledAutoModeration::compile(&create_config(&["ok", "(invalid", "no"]));
+        assert_eq!(compiled.errors().len(), 1);
+        assert!(compiled.is_rule_valid(0));
+        assert!(!compiled.is_rule_valid(1));
+
+        let matcher = compiled.matcher("ok");
+        assert_eq!(matcher.is_match(0, 0), None);
+        assert_eq!(matcher.is_match(0, 1), Some(true));
+        assert_eq!(matcher.is_match(1, 1), None);
+        assert_eq!(matcher.is_match(2, 1), Some(false));
+    }
+
+    #[test]
+    fn test_regex_set_matcher() {
+        let compiled = CompiledAutoModeration::compile(&create_config(&["a", "b", "c", "d", "e"]));
+        assert!(compiled.set.is_some());
+
+        let matcher = compiled.matcher("bed");
+        let matches = (0..5).map(|rule| matcher.is_match(rule, 1)).collect::<Vec<Option<bool>>>();
+        assert_eq!(matches, vec![Some(false), Some(true), Some(false), Some(true), Some(true)]);
+    }
+}
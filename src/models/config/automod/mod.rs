@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::models::config::automod::filters::FilterMetadata;
 use serde::{Serialize, Deserialize};
@@ -7,18 +8,86 @@ use twilight_model::id::marker::ChannelMarker;
 use self::actions::{ActionMetadata, BucketAction};
 use self::ignore::Ignore;
 use crate::models::config::automod::checks::Check;
+use crate::models::config::automod::compiled::CompiledAutoModeration;
+use crate::utils::urls::matches_domain;
 
 pub mod actions;
+pub mod blocklist;
 pub mod checks;
+pub mod compiled;
 pub mod filters;
 pub mod ignore;
 
+/// Suffixes under which anyone can register domains, besides top-level domains
+const PUBLIC_SUFFIXES: [&str; 24] = [
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "com.au", "net.au", "org.au", "co.nz", "co.jp", "co.in",
+    "co.za", "com.br", "com.cn", "com.mx", "com.tr", "github.io", "gitlab.io", "pages.dev", "workers.dev",
+    "vercel.app", "netlify.app", "web.app", "herokuapp.com", "blogspot.com"
+];
+
+/// Sites which are never listed, also with their subdomains
+const PROTECTED_DOMAINS: [&str; 20] = [
+    "discord.com", "discord.gg", "discordapp.com", "discordapp.net", "discord.media", "discord.gift",
+    "google.com", "youtube.com", "youtu.be", "twitter.com", "x.com", "github.com", "twitch.tv",
+    "reddit.com", "steamcommunity.com", "steampowered.com", "wikipedia.org", "microsoft.com",
+    "apple.com", "amazon.com"
+];
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AutoModeration {
     pub rules: Vec<AutoModerationRule>,
     pub bucket_actions: HashMap<String, BucketAction>,
     pub logs_channel: Option<Id<ChannelMarker>>,
-    pub ignore: Option<Ignore>
+    pub ignore: Option<Ignore>,
+    /// Domains flagged as scam links only in this guild, in addition to the shared list
+    #[serde(default)]
+    pub scam_domains: Vec<String>,
+    /// Set when the config is loaded into the cache, so regexes are compiled once per config change
+    #[serde(skip)]
+    pub compiled: Option<Arc<CompiledAutoModeration>>
+}
+
+impl AutoModeration {
+    pub fn compile(&mut self) {
+        self.compiled = Some(Arc::new(CompiledAutoModeration::compile(self)));
+    }
+
+    pub fn compiled(&self) -> Arc<CompiledAutoModeration> {
+        self.compiled.to_owned().unwrap_or_else(|| Arc::new(CompiledAutoModeration::compile(self)))
+    }
+
+    /// Returns the first scam domain which cannot be listed or the first pattern which cannot be compiled
+    pub fn validate(&self) -> Result<(), String> {
+        self.scam_domains.iter().try_for_each(|domain| validate_scam_domain(domain))?;
+
+        match CompiledAutoModeration::compile(self).errors().first() {
+            Some(error) => Err(error.to_owned()),
+            None => Ok(())
+        }
+    }
+}
+
+/// Domain which would flag links to a whole public suffix or to a popular site
+fn validate_scam_domain(domain: &str) -> Result<(), String> {
+    let normalized = domain.trim().trim_end_matches('.').to_lowercase();
+
+    let labels = normalized.split('.').collect::<Vec<&str>>();
+    let is_valid = labels.iter().all(|label| {
+        !label.is_empty() && label.chars().all(|char| char.is_ascii_alphanumeric() || char == '-')
+    });
+    if !is_valid || labels.len() < 2 {
+        return Err(format!("Scam domain `{domain}` isn't a valid domain"))
+    }
+
+    if PUBLIC_SUFFIXES.contains(&normalized.as_str()) {
+        return Err(format!("Scam domain `{domain}` is a public suffix"))
+    }
+
+    if PROTECTED_DOMAINS.iter().any(|protected| matches_domain(&normalized, protected)) {
+        return Err(format!("Scam domain `{domain}` belongs to a well-known site"))
+    }
+
+    Ok(())
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -45,4 +114,21 @@ pub struct AutoModerationRule {
 pub enum TrigerEvent {
     MessageCreate,
     MessageUpdate
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::models::config::automod::validate_scam_domain;
+
+    #[test]
+    fn test_validate_scam_domain() {
+        assert!(validate_scam_domain("free-nitro.com").is_ok());
+        assert!(validate_scam_domain("Scam.Co.UK.").is_ok());
+        assert!(validate_scam_domain("com").is_err());
+        assert!(validate_scam_domain("co.uk").is_err());
+        assert!(validate_scam_domain("github.io").is_err());
+        assert!(validate_scam_domain("Discord.com").is_err());
+        assert!(validate_scam_domain("cdn.discordapp.com").is_err());
+        assert!(validate_scam_domain("https://scam.com/login").is_err());
+        assert!(validate_scam_domain("").is_err());
+    }
+}
@@ -34,22 +34,39 @@ impl GuildConfig {
                 native_support: false,
                 logs_channel: None,
                 dm_case: false,
-                automod: None
+                automod: None,
+                escalations: vec![],
+                case_rules: vec![],
+                case_managers: vec![]
             },
             premium: false,
             levels: Levels {
                 xp_timeout: 0,
                 xp_min: 0,
-                xp_max: 0
+                xp_max: 0,
+                curve: Default::default(),
+                rewards: vec![],
+                rewards_mode: Default::default(),
+                announcement: None
             },
             top: Top {
                 week: false,
                 day: false,
+                month: false,
+                alltime: false,
                 webhook_url: "".to_string()
             }
         }
     }
 
+    /// Returns the reason why the config cannot be saved
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.moderation.automod {
+            Some(automod) => automod.validate(),
+            None => Ok(())
+        }
+    }
+
     pub fn get_bucket_action(&self, key: &str) -> Option<BucketAction> {
         self.moderation.automod.as_ref().map(|a| a.bucket_actions.get(key).cloned())?
     }
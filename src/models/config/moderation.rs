@@ -1,7 +1,9 @@
 use serde::{Serialize, Deserialize};
 use twilight_model::id::Id;
-use twilight_model::id::marker::{ChannelMarker, RoleMarker};
+use twilight_model::id::marker::{ChannelMarker, RoleMarker, UserMarker};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use mongodb::bson::DateTime;
+use crate::models::case::{CaseActionType, DEFAULT_CASE_POINTS};
 
 use super::automod::AutoModeration;
 
@@ -20,5 +22,176 @@ pub struct Moderation {
     pub mute_role: Option<Id<RoleMarker>>,
     pub native_support: bool,
     pub logs_channel: Option<Id<ChannelMarker>>,
-    pub dm_case: bool
-}
\ No newline at end of file
+    pub dm_case: bool,
+    #[serde(default)]
+    pub escalations: Vec<WarnEscalation>,
+    #[serde(default)]
+    pub case_rules: Vec<CaseRule>,
+    /// Roles allowed to edit, remove and restore cases created by other moderators
+    #[serde(default)]
+    pub case_managers: Vec<Id<RoleMarker>>
+}
+
+/// Expiry and weight of cases with the action
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CaseRule {
+    pub action: CaseActionType,
+    /// Seconds after which cases stop being active, they never expire when not set
+    #[serde(default)]
+    pub expires_after: Option<u64>,
+    #[serde(default = "default_points")]
+    pub points: u32
+}
+
+fn default_points() -> u32 {
+    DEFAULT_CASE_POINTS
+}
+
+/// Action taken automatically when a member collects enough warns
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WarnEscalation {
+    /// Sum of points of active warns which triggers the action
+    pub warns: u32,
+    /// Only warns from the last `period` seconds are counted
+    pub period: u64,
+    pub action: EscalationAction
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum EscalationAction {
+    /// Durations are in seconds
+    Timeout { duration: u64 },
+    Mute { duration: u64 },
+    Kick,
+    Ban { duration: Option<u64> }
+}
+
+impl Moderation {
+    /// Returns true when the member created the case or has one of the case manager roles
+    pub fn can_manage_case(
+        &self,
+        case_moderator_id: Id<UserMarker>,
+        user_id: Id<UserMarker>,
+        roles: &[Id<RoleMarker>]
+    ) -> bool {
+        case_moderator_id == user_id || roles.iter().any(|role| self.case_managers.contains(role))
+    }
+
+    /// Returns the expiration time and points of a new case with the action
+    pub fn get_case_expiry(&self, action: &CaseActionType, created_at: DateTime) -> (Option<DateTime>, u32) {
+        match self.case_rules.iter().find(|rule| &rule.action == action) {
+            Some(rule) => {
+                let expires_at = rule.expires_after.map(|expires_after| DateTime::from_millis(
+                    created_at.timestamp_millis().saturating_add((expires_after as i64).saturating_mul(1000))
+                ));
+                (expires_at, rule.points)
+            },
+            None => (None, action.default_points())
+        }
+    }
+
+    /// Returns the escalation reached by the member with numbers of the warns counted for it.
+    /// `warns` are numbers, creation times (in milliseconds) and points of active warns of the member,
+    /// the last one is the warn which was just given
+    pub fn get_escalation(&self, warns: &[(u64, i64, u32)], now: i64) -> Option<(&WarnEscalation, Vec<u64>)> {
+        let (_, _, last_points) = warns.last()?;
+
+        self.escalations.iter()
+            .filter_map(|escalation| {
+                let period = (escalation.period as i64).saturating_mul(1000);
+                let counted = warns.iter()
+                    .filter(|(_, created_at, _)| now - created_at <= period)
+                    .collect::<Vec<&(u64, i64, u32)>>();
+                let points = counted.iter().map(|(_, _, points)| *points as u64).sum::<u64>();
+
+                // the action is taken once, when the warn reaching the limit is given
+                let limit = escalation.warns as u64;
+                let is_reached = points >= limit && points - (*last_points as u64) < limit;
+                is_reached.then(|| (escalation, counted.iter().map(|(index, _, _)| *index).collect()))
+            })
+            .max_by_key(|(escalation, _)| escalation.warns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::config::GuildConfig;
+    use crate::models::case::CaseActionType;
+    use crate::models::config::moderation::{CaseRule, EscalationAction, WarnEscalation};
+    use mongodb::bson::DateTime;
+    use twilight_model::id::Id;
+
+    const DAY: i64 = 24 * 60 * 60 * 1000;
+
+    #[test]
+    fn test_get_escalation() {
+        let mut moderation = GuildConfig::new(Id::new(1)).moderation;
+        moderation.escalations = vec![
+            WarnEscalation {
+                warns: 3,
+                period: 7 * 24 * 60 * 60,
+                action: EscalationAction::Timeout { duration: 3600 }
+            },
+            WarnEscalation {
+                warns: 5,
+                period: 30 * 24 * 60 * 60,
+                action: EscalationAction::Ban { duration: None }
+            }
+        ];
+        let now = 100 * DAY;
+
+        assert_eq!(moderation.get_escalation(&[], now), None);
+
+        let warns = [(1, now - 20 * DAY, 1), (2, now - 2 * DAY, 1), (3, now - DAY, 1)];
+        assert_eq!(moderation.get_escalation(&warns, now), None);
+
+        let warns = [(1, now - 20 * DAY, 1), (2, now - 2 * DAY, 1), (3, now - DAY, 1), (4, now, 1)];
+        let (escalation, counted) = moderation.get_escalation(&warns, now).unwrap();
+        assert_eq!(escalation.action, EscalationAction::Timeout { duration: 3600 });
+        assert_eq!(counted, vec![2, 3, 4]);
+
+        let warns = [
+            (1, now - 40 * DAY, 1), (2, now - 20 * DAY, 1), (3, now - 10 * DAY, 1),
+            (4, now - 2 * DAY, 1), (5, now - DAY, 1), (6, now, 1)
+        ];
+        let (escalation, counted) = moderation.get_escalation(&warns, now).unwrap();
+        assert_eq!(escalation.action, EscalationAction::Ban { duration: None });
+        assert_eq!(counted, vec![2, 3, 4, 5, 6]);
+
+        // a heavy warn reaches the ban at once and the next one doesn't trigger anything
+        let warns = [(1, now - DAY, 1), (2, now, 4)];
+        let (escalation, _) = moderation.get_escalation(&warns, now).unwrap();
+        assert_eq!(escalation.action, EscalationAction::Ban { duration: None });
+        let warns = [(1, now - 2 * DAY, 1), (2, now - DAY, 4), (3, now, 1)];
+        assert_eq!(moderation.get_escalation(&warns, now), None);
+
+        let warns = [(1, now - DAY, 2), (2, now, 2)];
+        let (escalation, _) = moderation.get_escalation(&warns, now).unwrap();
+        assert_eq!(escalation.action, EscalationAction::Timeout { duration: 3600 });
+    }
+
+    #[test]
+    fn test_can_manage_case() {
+        let mut moderation = GuildConfig::new(Id::new(1)).moderation;
+        moderation.case_managers = vec![Id::new(10)];
+
+        assert!(moderation.can_manage_case(Id::new(1), Id::new(1), &[]));
+        assert!(!moderation.can_manage_case(Id::new(1), Id::new(2), &[Id::new(11)]));
+        assert!(moderation.can_manage_case(Id::new(1), Id::new(2), &[Id::new(11), Id::new(10)]));
+    }
+
+    #[test]
+    fn test_get_case_expiry() {
+        let mut moderation = GuildConfig::new(Id::new(1)).moderation;
+        moderation.case_rules = vec![CaseRule { action: CaseActionType::Unban, expires_after: Some(60), points: 2 }];
+        let created_at = DateTime::from_millis(DAY);
+
+        assert_eq!(moderation.get_case_expiry(&CaseActionType::Warn, created_at), (None, 1));
+        assert_eq!(moderation.get_case_expiry(&CaseActionType::Untimeout, created_at), (None, 0));
+        assert_eq!(
+            moderation.get_case_expiry(&CaseActionType::Unban, created_at),
+            (Some(DateTime::from_millis(DAY + 60_000)), 2)
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use mongodb::bson::{doc, DateTime, Document};
+use serde::Serialize;
+use twilight_http::Client;
+use twilight_model::id::Id;
+use twilight_model::id::marker::{GuildMarker, UserMarker};
+use crate::models::case::Case;
+
+/// Distinct users whose names are fetched during a single export, the rest is exported without names
+const MAX_RESOLVED_USERS: usize = 100;
+
+const CSV_HEADER: &str = "index,action,member_id,member_name,moderator_id,moderator_name,reason,created_at,duration,expires_at,points,removed,related_cases\n";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json"
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json => "application/json"
+        }
+    }
+
+    /// Text written before the first case
+    pub fn start(&self) -> &'static str {
+        match self {
+            Self::Csv => CSV_HEADER,
+            Self::Json => "["
+        }
+    }
+
+    /// Text written after the last case
+    pub fn end(&self) -> &'static str {
+        match self {
+            Self::Csv => "",
+            Self::Json => "]"
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CaseExportFilter {
+    pub member_id: Option<Id<UserMarker>>,
+    pub from: Option<DateTime>,
+    /// Exclusive end of the range
+    pub to: Option<DateTime>
+}
+
+impl CaseExportFilter {
+    pub fn to_document(&self, guild_id: Id<GuildMarker>) -> Document {
+        let mut filter = doc! { "guild_id": guild_id.to_string() };
+
+        if let Some(member_id) = self.member_id {
+            filter.insert("member_id", member_id.to_string());
+        }
+
+        let mut created_at = Document::new();
+        if let Some(from) = self.from { created_at.insert("$gte", from); }
+        if let Some(to) = self.to { created_at.insert("$lt", to); }
+        if !created_at.is_empty() { filter.insert("created_at", created_at); }
+
+        filter
+    }
+}
+
+#[derive(Serialize)]
+struct CaseRecord {
+    index: u64,
+    action: String,
+    member_id: String,
+    member_name: Option<String>,
+    moderator_id: String,
+    moderator_name: Option<String>,
+    reason: Option<String>,
+    created_at: Option<String>,
+    duration: Option<i64>,
+    expires_at: Option<String>,
+    points: u32,
+    removed: bool,
+    related_cases: Vec<u64>
+}
+
+impl CaseRecord {
+    fn to_csv_line(&self) -> String {
+        let optional = |value: &Option<String>| value.as_deref().map(escape_csv).unwrap_or_default();
+
+        let mut line = [
+            self.index.to_string(),
+            escape_csv(&self.action),
+            self.member_id.to_owned(),
+            optional(&self.member_name),
+            self.moderator_id.to_owned(),
+            optional(&self.moderator_name),
+            optional(&self.reason),
+            optional(&self.created_at),
+            self.duration.map(|duration| duration.to_string()).unwrap_or_default(),
+            optional(&self.expires_at),
+            self.points.to_string(),
+            self.removed.to_string(),
+            self.related_cases.iter().map(u64::to_string).collect::<Vec<String>>().join(" ")
+        ].join(",");
+
+        line.push('\n');
+        line
+    }
+}
+
+/// Converts cases into the export format, names of users are fetched once per export
+pub struct CaseExporter {
+    format: ExportFormat,
+    discord_http: Arc<Client>,
+    names: HashMap<Id<UserMarker>, Option<String>>,
+    written: usize
+}
+
+impl CaseExporter {
+    pub fn new(format: ExportFormat, discord_http: Arc<Client>) -> Self {
+        Self {
+            format,
+            discord_http,
+            names: HashMap::new(),
+            written: 0
+        }
+    }
+
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    async fn get_name(&mut self, user_id: Id<UserMarker>) -> Option<String> {
+        if let Some(name) = self.names.get(&user_id) {
+            return name.to_owned()
+        }
+
+        if self.names.len() >= MAX_RESOLVED_USERS { return None }
+
+        let name = match self.discord_http.user(user_id).await {
+            Ok(user) => user.model().await.ok().map(|user| user.name),
+            Err(_) => None
+        };
+
+        self.names.insert(user_id, name.to_owned());
+        name
+    }
+
+    /// Returns the case in the export format, including the separator from the previous case
+    pub async fn write(&mut self, case: &Case) -> String {
+        let record = CaseRecord {
+            index: case.index,
+            action: format!("{:?}", case.action),
+            member_id: case.member_id.to_string(),
+            member_name: self.get_name(case.member_id).await,
+            moderator_id: case.moderator_id.to_string(),
+            moderator_name: self.get_name(case.moderator_id).await,
+            reason: case.reason.to_owned(),
+            created_at: case.created_at.try_to_rfc3339_string().ok(),
+            duration: case.duration,
+            expires_at: case.expires_at.and_then(|expires_at| expires_at.try_to_rfc3339_string().ok()),
+            points: case.points,
+            removed: case.removed,
+            related_cases: case.related_cases.to_owned()
+        };
+
+        let separator = if self.written > 0 && self.format == ExportFormat::Json { "," } else { "" };
+        self.written += 1;
+
+        match self.format {
+            ExportFormat::Csv => record.to_csv_line(),
+            ExportFormat::Json => format!(
+                "{separator}{}", serde_json::to_string(&record).unwrap_or_else(|_| "null".to_string())
+            )
+        }
+    }
+}
+
+/// Quotes the value when needed and prevents spreadsheets from evaluating it as a formula
+fn escape_csv(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{value}")
+    } else { value.to_string() };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else { value }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::export::escape_csv;
+
+    #[test]
+    fn test_escape_csv() {
+        assert_eq!(escape_csv("spam"), "spam");
+        assert_eq!(escape_csv("spam, links"), "\"spam, links\"");
+        assert_eq!(escape_csv("said \"hi\""), "\"said \"\"hi\"\"\"");
+        assert_eq!(escape_csv("=1+1"), "'=1+1");
+        assert_eq!(escape_csv("line\nbreak"), "\"line\nbreak\"");
+    }
+}
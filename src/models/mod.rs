@@ -1,3 +1,5 @@
 pub mod config;
 pub mod case;
-pub mod task;
\ No newline at end of file
+pub mod task;
+pub mod top;
+pub mod export;
\ No newline at end of file
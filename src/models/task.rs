@@ -1,17 +1,87 @@
 use mongodb::bson::DateTime;
+use mongodb::bson::oid::ObjectId;
+use twilight_model::channel::message::AllowedMentions;
 use twilight_model::id::Id;
-use twilight_model::id::marker::{GuildMarker, UserMarker};
+use twilight_model::id::marker::{ChannelMarker, GuildMarker, MessageMarker, RoleMarker, UserMarker};
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize)]
 pub struct Task {
+    #[serde(rename = "_id", default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
     pub execute_at: DateTime,
     pub guild_id: Id<GuildMarker>,
-    pub action: TaskAction
+    pub action: TaskAction,
+    /// Number of failed executions
+    #[serde(default)]
+    pub attempts: u32,
+    /// The task is claimed by a scheduler until this time
+    #[serde(default)]
+    pub locked_until: Option<DateTime>,
+    /// Set when the task failed too many times, it's kept only as a record and never executed again
+    #[serde(default)]
+    pub failed_at: Option<DateTime>
+}
+
+impl Task {
+    pub fn new(guild_id: Id<GuildMarker>, execute_at: DateTime, action: TaskAction) -> Self {
+        Self {
+            id: None,
+            execute_at,
+            guild_id,
+            action,
+            attempts: 0,
+            locked_until: None,
+            failed_at: None
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub enum TaskAction {
     RemoveMuteRole(Id<UserMarker>),
-    RemoveBan(Id<UserMarker>)
-}
\ No newline at end of file
+    RemoveBan(Id<UserMarker>),
+    /// Removes a role given for a limited time
+    RemoveRole {
+        member_id: Id<UserMarker>,
+        role_id: Id<RoleMarker>
+    },
+    DeleteMessage {
+        channel_id: Id<ChannelMarker>,
+        message_id: Id<MessageMarker>
+    },
+    /// Sends a scheduled announcement
+    SendMessage {
+        channel_id: Id<ChannelMarker>,
+        content: String,
+        /// Decided when the message is scheduled, nothing is pinged by default
+        #[serde(default)]
+        allowed_mentions: AllowedMentions
+    },
+    /// Sends the reminder to the channel where it was created and mentions the member
+    Remind {
+        member_id: Id<UserMarker>,
+        channel_id: Id<ChannelMarker>,
+        content: String
+    }
+}
+
+impl TaskAction {
+    /// Short description shown in the list of pending tasks
+    pub fn describe(&self) -> String {
+        match self {
+            TaskAction::RemoveMuteRole(member_id) => format!("Unmute <@{member_id}>"),
+            TaskAction::RemoveBan(member_id) => format!("Unban <@{member_id}>"),
+            TaskAction::RemoveRole { member_id, role_id } => {
+                format!("Remove <@&{role_id}> from <@{member_id}>")
+            },
+            TaskAction::DeleteMessage { channel_id, message_id } => {
+                format!("Delete message `{message_id}` in <#{channel_id}>")
+            },
+            TaskAction::SendMessage { channel_id, .. } => format!("Send announcement to <#{channel_id}>"),
+            TaskAction::Remind { member_id, channel_id, .. } => {
+                format!("Remind <@{member_id}> in <#{channel_id}>")
+            }
+        }
+    }
+}
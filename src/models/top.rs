@@ -0,0 +1,19 @@
+use mongodb::bson::DateTime;
+use twilight_model::id::Id;
+use twilight_model::id::marker::{GuildMarker, UserMarker};
+use serde::{Serialize, Deserialize};
+
+/// Ranking of the finished `top_day`/`top_week` period
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TopArchive {
+    pub guild_id: Id<GuildMarker>,
+    pub period: String,
+    pub ended_at: DateTime,
+    pub ranking: Vec<TopArchiveEntry>
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TopArchiveEntry {
+    pub user_id: Id<UserMarker>,
+    pub messages: u32
+}
@@ -0,0 +1,149 @@
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use reqwest::Url;
+use reqwest::redirect::Policy;
+use tokio::time::Instant;
+use crate::utils::urls::{domain, matches_domain};
+
+pub type Resolver = Arc<dyn LinkResolver>;
+
+/// Maximum number of redirects followed when resolving a link
+const MAX_REDIRECTS: usize = 5;
+
+/// How long resolved links are remembered
+const CACHE_DURATION: Duration = Duration::from_secs(60 * 60);
+
+/// Expired links are removed when the cache grows over this size, it's cleared when all of them are still fresh
+const MAX_CACHE_SIZE: usize = 10_000;
+
+const SHORTENER_DOMAINS: [&str; 14] = [
+    "bit.ly", "tinyurl.com", "t.co", "goo.gl", "ow.ly", "is.gd", "buff.ly", "cutt.ly",
+    "rebrand.ly", "shorturl.at", "tiny.cc", "rb.gy", "t.ly", "s.id"
+];
+
+pub fn is_shortener(url: &Url) -> bool {
+    domain(url)
+        .map(|domain| SHORTENER_DOMAINS.iter().any(|shortener| matches_domain(&domain, shortener)))
+        .unwrap_or(false)
+}
+
+#[async_trait]
+pub trait LinkResolver: Send + Sync {
+    /// Returns the final target of the link, `None` when it couldn't be resolved
+    async fn resolve(&self, url: &Url) -> Option<Url>;
+}
+
+/// Follows redirects of shortened links with HEAD requests. Only the shorteners are requested,
+/// the first location outside of them is returned without loading it
+pub struct HttpResolver {
+    client: reqwest::Client,
+    cache: DashMap<String, (Instant, Option<Url>)>
+}
+
+impl HttpResolver {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .timeout(Duration::from_secs(5))
+            .build()
+            .expect("Cannot build resolver client");
+
+        Self { client, cache: DashMap::new() }
+    }
+
+    async fn follow(&self, url: &Url) -> Option<Url> {
+        let mut url = url.to_owned();
+
+        for _ in 0..MAX_REDIRECTS {
+            if !is_shortener(&url) { return Some(url) }
+            if !is_public_host(&url).await { return None }
+
+            let response = self.client.head(url.to_owned()).send().await.ok()?;
+            if !response.status().is_redirection() { return Some(url) }
+
+            let location = response.headers().get("Location")?.to_str().ok()?;
+            // the location can be relative to the current URL
+            url = url.join(location).ok()?;
+        }
+
+        Some(url)
+    }
+}
+
+#[async_trait]
+impl LinkResolver for HttpResolver {
+    async fn resolve(&self, url: &Url) -> Option<Url> {
+        if let Some(cached) = self.cache.get(url.as_str()) {
+            if cached.0 > Instant::now() { return cached.1.to_owned() }
+        }
+
+        let target = self.follow(url).await;
+
+        if self.cache.len() >= MAX_CACHE_SIZE {
+            let now = Instant::now();
+            self.cache.retain(|_, (expires_at, _)| *expires_at > now);
+            if self.cache.len() >= MAX_CACHE_SIZE { self.cache.clear() }
+        }
+        self.cache.insert(url.to_string(), (Instant::now() + CACHE_DURATION, target.to_owned()));
+
+        target
+    }
+}
+
+/// Whether all addresses of the host are public, so shorteners can't be used to reach internal services
+async fn is_public_host(url: &Url) -> bool {
+    let Some(host) = url.host_str() else { return false };
+    let Ok(addresses) = tokio::net::lookup_host((host, url.port_or_known_default().unwrap_or(443))).await else {
+        return false
+    };
+
+    let mut addresses = addresses.peekable();
+    addresses.peek().is_some() && addresses.all(|address| is_public_ip(address.ip()))
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified()
+                || ip.is_broadcast() || ip.is_documentation() || ip.is_multicast()
+                // shared address space, 100.64.0.0/10
+                || (first == 100 && second & 0xC0 == 64))
+        },
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast()
+                    // unique local (fc00::/7) and link-local (fe80::/10) addresses
+                    || first & 0xFE00 == 0xFC00 || first & 0xFFC0 == 0xFE80)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+    use crate::resolver::is_public_ip;
+
+    #[test]
+    fn test_is_public_ip() {
+        let is_public = |ip: &str| is_public_ip(ip.parse::<IpAddr>().unwrap());
+
+        assert!(is_public("1.1.1.1"));
+        assert!(is_public("2606:4700::1111"));
+        assert!(!is_public("127.0.0.1"));
+        assert!(!is_public("10.0.0.1"));
+        assert!(!is_public("192.168.1.1"));
+        assert!(!is_public("169.254.169.254"));
+        assert!(!is_public("100.100.100.200"));
+        assert!(!is_public("::1"));
+        assert!(!is_public("fd00::1"));
+        assert!(!is_public("fe80::1"));
+        assert!(!is_public("::ffff:127.0.0.1"));
+    }
+}
@@ -12,6 +12,12 @@ pub enum Rejection {
     InvalidSignature,
     #[cfg(feature = "api")]
     Unauthorized,
+    #[cfg(feature = "api")]
+    Forbidden,
+    #[cfg(feature = "api")]
+    BadRequest(&'static str),
+    #[cfg(feature = "api")]
+    InvalidConfig(String),
     Internal(anyhow::Error)
 }
 
@@ -24,6 +30,12 @@ impl Display for Rejection {
             Rejection::InvalidSignature => f.write_str("Couldn't verify signature"),
             #[cfg(feature = "api")]
             Rejection::Unauthorized => f.write_str("Invalid authorization data provided"),
+            #[cfg(feature = "api")]
+            Rejection::Forbidden => f.write_str("Missing permissions to access this resource"),
+            #[cfg(feature = "api")]
+            Rejection::BadRequest(message) => f.write_str(message),
+            #[cfg(feature = "api")]
+            Rejection::InvalidConfig(message) => f.write_str(message),
             Rejection::Internal(err) => std::fmt::Display::fmt(&err, f),
         }?;
         Ok(())
@@ -68,6 +80,12 @@ pub async fn handle_rejection(rejection: warp::Rejection) -> Result<impl Reply,
             Rejection::InvalidSignature => StatusCode::BAD_REQUEST,
             #[cfg(feature = "api")]
             Rejection::Unauthorized => StatusCode::UNAUTHORIZED,
+            #[cfg(feature = "api")]
+            Rejection::Forbidden => StatusCode::FORBIDDEN,
+            #[cfg(feature = "api")]
+            Rejection::BadRequest(_) => StatusCode::BAD_REQUEST,
+            #[cfg(feature = "api")]
+            Rejection::InvalidConfig(_) => StatusCode::BAD_REQUEST,
             _ => StatusCode::INTERNAL_SERVER_ERROR
         })
     } else {
@@ -45,8 +45,14 @@ async fn handle_command(
             let bytes_data = serde_json::to_vec(&response_data)
                 .expect("Interaction response serialization error");
 
-            discord_http.execute_webhook(id, &token)
-                .payload_json(bytes_data.as_slice()).await.ok();
+            // files aren't a part of the JSON payload, so they are uploaded separately
+            let attachments = response_data.attachments.unwrap_or_default();
+
+            let webhook = discord_http.execute_webhook(id, &token)
+                .payload_json(bytes_data.as_slice())
+                .attachments(&attachments);
+
+            if let Ok(webhook) = webhook { webhook.await.ok(); }
         });
 
         Ok((InteractionResponseData {
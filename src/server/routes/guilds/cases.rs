@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use serde::Deserialize;
+use twilight_http::Client;
+use twilight_model::id::Id;
+use twilight_model::id::marker::{GuildMarker, UserMarker};
+use warp::{Filter, Reply};
+use warp::http::Response;
+use warp::hyper::Body;
+use crate::commands::case::parse_date;
+use crate::context::Context;
+use crate::models::export::{CaseExporter, CaseExportFilter, ExportFormat};
+use crate::{response_type, with_value};
+use crate::server::error::{MapErrorIntoInternalRejection, Rejection};
+use crate::server::routes::guilds::{check_guild_permissions, GuildId};
+use crate::server::session::{Authenticator, AuthorizationInformation, authorize_user, Sessions};
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+    member: Option<Id<UserMarker>>,
+    /// `YYYY-MM-DD`
+    from: Option<String>,
+    /// `YYYY-MM-DD`, inclusive
+    to: Option<String>
+}
+
+pub fn export(
+    discord_http: Arc<Client>,
+    context: Arc<Context>,
+    authenticator: Arc<Authenticator>,
+    sessions: Arc<Sessions>
+) -> response_type!() {
+    let with_discord_http = with_value!(discord_http);
+    let with_context = with_value!(context);
+
+    warp::get()
+        .and(warp::path!("guilds" / GuildId / "cases"))
+        .and(warp::query::<ExportQuery>())
+        .and(authorize_user(authenticator, sessions))
+        .and(with_discord_http)
+        .and(with_context)
+        .and_then(run)
+}
+
+async fn run(
+    guild_id: Id<GuildMarker>,
+    query: ExportQuery,
+    info: Arc<AuthorizationInformation>,
+    discord_http: Arc<Client>,
+    context: Arc<Context>
+) -> Result<impl Reply, warp::Rejection> {
+    check_guild_permissions(&info, guild_id).await?;
+
+    let format = match query.format.as_deref() {
+        Some(format) => ExportFormat::parse(format)
+            .ok_or_else(|| reject!(Rejection::BadRequest("Unknown export format")))?,
+        None => ExportFormat::Csv
+    };
+
+    let parse = |date: &Option<String>, end_of_day: bool| date.as_deref()
+        .map(|date| parse_date(date, end_of_day))
+        .transpose()
+        .map_err(|_| reject!(Rejection::BadRequest("Invalid date, expected format is YYYY-MM-DD")));
+
+    let filter = CaseExportFilter {
+        member_id: query.member,
+        from: parse(&query.from, false)?,
+        to: parse(&query.to, true)?
+    };
+
+    let cases = context.mongodb.export_cases(guild_id, &filter)
+        .await.map_err(|_| reject!(Rejection::Internal(anyhow::anyhow!("Cannot fetch cases"))))?;
+
+    // cases are written as they are read from the database, so the export isn't kept in memory
+    let exporter = CaseExporter::new(format, discord_http);
+    let body = stream::once(async move { Ok::<String, mongodb::error::Error>(format.start().to_string()) })
+        .chain(
+            stream::try_unfold((cases, exporter), |(mut cases, mut exporter)| async move {
+                Ok(match cases.try_next().await? {
+                    Some(case) => Some((exporter.write(&case).await, (cases, exporter))),
+                    None => None
+                })
+            })
+        )
+        .chain(stream::once(async move { Ok(format.end().to_string()) }));
+
+    Response::builder()
+        .header("Content-Type", format.content_type())
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"cases-{guild_id}.{}\"", format.extension())
+        )
+        .body(Body::wrap_stream(body))
+        .map_rejection()
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use reqwest::StatusCode;
+use twilight_model::id::Id;
+use twilight_model::id::marker::GuildMarker;
+use warp::{Filter, Reply};
+use crate::context::Context;
+use crate::models::config::GuildConfig;
+use crate::{response_type, with_value};
+use crate::server::error::{MapErrorIntoInternalRejection, Rejection};
+use crate::server::routes::guilds::{check_guild_permissions, GuildId};
+use crate::server::session::{Authenticator, AuthorizationInformation, authorize_user, Sessions};
+
+pub fn update(context: Arc<Context>, authenticator: Arc<Authenticator>, sessions: Arc<Sessions>) -> response_type!() {
+    let with_context = with_value!(context);
+
+    warp::put()
+        .and(warp::path!("guilds" / GuildId / "config"))
+        .and(warp::body::json::<GuildConfig>())
+        .and(authorize_user(authenticator, sessions))
+        .and(with_context)
+        .and_then(run)
+}
+
+async fn run(
+    guild_id: Id<GuildMarker>,
+    mut config: GuildConfig,
+    info: Arc<AuthorizationInformation>,
+    context: Arc<Context>
+) -> Result<impl Reply, warp::Rejection> {
+    check_guild_permissions(&info, guild_id).await?;
+
+    // custom clients and premium are assigned by the bot, so they cannot be changed here
+    let current = context.mongodb.get_config(guild_id).await.map_rejection()?;
+    config.guild_id = guild_id;
+    config.application_id = current.application_id;
+    config.premium = current.premium;
+
+    config.validate().map_err(|error| reject!(Rejection::InvalidConfig(error)))?;
+
+    context.mongodb.save_config(&context.redis, config)
+        .await.map_err(|_| reject!(Rejection::Internal(anyhow::anyhow!("Cannot save config"))))?;
+
+    Ok(warp::reply::with_status("", StatusCode::NO_CONTENT))
+}
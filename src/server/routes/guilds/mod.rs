@@ -1,15 +1,21 @@
 use std::sync::Arc;
 use serde::{Deserialize, Serialize};
+use twilight_model::guild::Permissions;
 use twilight_model::id::Id;
 use twilight_model::id::marker::GuildMarker;
 use twilight_model::user::CurrentUserGuild;
 use warp::{Filter, Reply};
 use crate::context::Context;
 use crate::{response_type, with_value};
-use crate::server::error::MapErrorIntoInternalRejection;
+use crate::server::error::{MapErrorIntoInternalRejection, Rejection};
 use crate::server::session::{Authenticator, AuthorizationInformation, authorize_user, Sessions};
 
 mod _id;
+pub mod cases;
+pub mod config;
+
+/// `warp::path!` doesn't accept generic types, so guild ids in paths use this alias
+pub type GuildId = Id<GuildMarker>;
 
 #[derive(Serialize, Deserialize)]
 struct Response {
@@ -49,4 +55,21 @@ async fn run(
         .collect();
 
     Ok(warp::reply::json(&Response { guilds, mutual }))
+}
+
+/// Allows only users who can manage the guild
+pub async fn check_guild_permissions(
+    info: &AuthorizationInformation,
+    guild_id: Id<GuildMarker>
+) -> Result<(), warp::Rejection> {
+    let guilds = info.http.current_user_guilds()
+        .await.map_rejection()?.model().await.map_rejection()?;
+
+    let can_manage = guilds.iter().any(|guild| {
+        guild.id == guild_id
+            && (guild.permissions.contains(Permissions::ADMINISTRATOR)
+                || guild.permissions.contains(Permissions::MANAGE_GUILD))
+    });
+
+    if can_manage { Ok(()) } else { err!(Rejection::Forbidden) }
 }
\ No newline at end of file
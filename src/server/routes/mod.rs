@@ -30,7 +30,7 @@ pub fn get_all_routes(
 
     #[cfg(feature = "http-interactions")]
     let filter = filter.or(interactions::filter(
-        discord_http, context.to_owned(), public_key
+        discord_http.to_owned(), context.to_owned(), public_key
     ));
 
     #[cfg(feature = "api")]
@@ -45,6 +45,9 @@ pub fn get_all_routes(
     let filter = filter
         .or(login::login(authenticator.to_owned(), sessions.to_owned()))
         .or(users::me::run(authenticator.to_owned(), sessions.to_owned()))
+        // registered before the list, which matches every path starting with /guilds
+        .or(guilds::cases::export(discord_http, context.to_owned(), authenticator.to_owned(), sessions.to_owned()))
+        .or(guilds::config::update(context.to_owned(), authenticator.to_owned(), sessions.to_owned()))
         .or(guilds::list(context, authenticator, sessions));
 
     filter
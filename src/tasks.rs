@@ -1,61 +1,181 @@
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
+use chrono::{Datelike, TimeZone, Utc};
 use mongodb::bson::DateTime;
+use reqwest::Url;
+use futures_util::StreamExt;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::UnboundedSender;
 use tokio::task::JoinHandle;
-use tokio::time::Instant;
 use twilight_http::Client;
+use twilight_http::api_error::{ApiError, GeneralApiError};
+use twilight_http::error::ErrorType;
+use twilight_model::channel::message::AllowedMentions;
 use twilight_model::id::Id;
-use twilight_model::id::marker::RoleMarker;
+use twilight_model::id::marker::{ChannelMarker, RoleMarker, WebhookMarker};
+use crate::commands::top::all::leaderboard_to_string;
+use crate::commands::top::period_name;
 use crate::database::mongodb::MongoDBConnection;
-use crate::ok_or_skip;
+use crate::database::redis::RedisConnection;
+use crate::{ok_or_skip, ok_or_skip_without_clone};
 use crate::models::config::GuildConfig;
 use crate::models::task::{Task, TaskAction};
+use crate::models::top::{TopArchive, TopArchiveEntry};
+use crate::utils::embeds::EmbedBuilder;
 use crate::gateway::clients::DiscordClients;
 
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+/// Maximum time in milliseconds between checks for due tasks
+const POLL_INTERVAL: i64 = 60 * 1000;
+/// Time in milliseconds for which a claimed task is hidden from other schedulers
+const TASK_LEASE: i64 = 5 * 60 * 1000;
+/// Delay in milliseconds before the first retry of a failed task, doubled with every next one
+const RETRY_BASE_DELAY: i64 = 60 * 1000;
+/// Tasks which failed this many times are marked as failed and not executed again
+const MAX_TASK_ATTEMPTS: u32 = 5;
+/// Discord error code returned for members who aren't in the guild
+const UNKNOWN_MEMBER_CODE: u64 = 10007;
+
 pub fn run(
     mongodb: MongoDBConnection,
+    redis: RedisConnection,
     discord_clients: DiscordClients,
     discord_http: Arc<Client>
 ) -> JoinHandle<()> {
-    tokio::spawn(interval(mongodb, discord_clients, discord_http))
+    tokio::spawn(async move {
+        tokio::join!(
+            interval(mongodb.to_owned(), redis.to_owned(), discord_clients, discord_http.to_owned()),
+            top_interval(mongodb, redis, discord_http)
+        );
+    })
 }
 
 pub async fn interval(
     mongodb: MongoDBConnection,
+    redis: RedisConnection,
     discord_clients: DiscordClients,
     discord_http: Arc<Client>
 ) {
+    let (wake_sender, mut wake_receiver) = mpsc::unbounded_channel();
+    tokio::spawn(listen_for_tasks(redis, wake_sender));
+
     loop {
-        let tasks = mongodb.get_and_delete_future_tasks(60 * 1000).await;
-
-        if let Ok(tasks) = tasks {
-            if !tasks.is_empty() {
-                println!("Loaded {} tasks", tasks.len())
-            };
-
-            for task in tasks {
-                let guild_config = ok_or_skip!(mongodb.get_config(task.guild_id).await, Ok);
-                let guild_discord_http = guild_config.application_id
-                    .and_then(|id| {
-                        discord_clients.get(&id)
-                            .map(|http| http.to_owned())
-                    }).unwrap_or_else(|| discord_http.to_owned());
-
-                tokio::spawn(execute_task(task, guild_config, guild_discord_http));
+        while let Ok(Some(task)) = mongodb.claim_due_task(TASK_LEASE).await {
+            let guild_config = ok_or_skip!(mongodb.get_config(task.guild_id).await, Ok);
+            let guild_discord_http = guild_config.application_id
+                .and_then(|id| {
+                    discord_clients.get(&id)
+                        .map(|http| http.to_owned())
+                }).unwrap_or_else(|| discord_http.to_owned());
+
+            tokio::spawn(execute_task(mongodb.to_owned(), task, guild_config, guild_discord_http));
+        }
+
+        let now = DateTime::now().timestamp_millis();
+        let next_poll_at = mongodb.get_next_task_time().await.ok().flatten()
+            .map(|execute_at| execute_at.timestamp_millis())
+            .unwrap_or(i64::MAX)
+            .min(now + POLL_INTERVAL);
+
+        let sleep = tokio::time::sleep(Duration::from_millis((next_poll_at - now).max(0) as u64));
+        tokio::pin!(sleep);
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                Some(execute_at) = wake_receiver.recv() => {
+                    // a new task is due before the scheduler would wake up
+                    if execute_at < next_poll_at { break }
+                }
             }
         }
+    };
+}
+
+/// Forwards execution times of created tasks from Redis, reconnects when the subscription ends
+async fn listen_for_tasks(redis: RedisConnection, wake_sender: UnboundedSender<i64>) {
+    loop {
+        if let Ok(pubsub) = redis.subscribe_tasks().await {
+            let mut messages = pubsub.into_on_message();
+            while let Some(message) = messages.next().await {
+                let execute_at = ok_or_skip_without_clone!(message.get_payload::<i64>(), Ok);
+                if wake_sender.send(execute_at).is_err() { return }
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
 
-        tokio::time::sleep(Duration::from_secs(60)).await;
+/// Runs the claimed task, it's removed after success and scheduled again with a backoff after failure
+pub async fn execute_task(mongodb: MongoDBConnection, task: Task, config: GuildConfig, discord_http: Arc<Client>) {
+    let id = match task.id {
+        Some(id) => id,
+        None => return
     };
+    let attempts = task.attempts + 1;
+    let guild_id = task.guild_id;
+    let description = task.action.describe();
+    let logs_channel = config.moderation.logs_channel;
+
+    let run = run_action(task, config, discord_http.to_owned());
+    tokio::pin!(run);
+
+    // the lease is extended while the task runs, so other schedulers don't claim it again
+    let mut heartbeat = tokio::time::interval(Duration::from_millis((TASK_LEASE / 2) as u64));
+    heartbeat.tick().await;
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            _ = heartbeat.tick() => { mongodb.extend_task_lease(id, TASK_LEASE).await.ok(); }
+        }
+    };
+
+    if result.is_ok() {
+        mongodb.complete_task(id).await.ok();
+        return
+    }
+
+    if attempts >= MAX_TASK_ATTEMPTS {
+        mongodb.fail_task(id).await.ok();
+        // the guild is told that the action was dropped, without the logs channel it's only in the bot logs
+        let reported = match logs_channel {
+            Some(channel_id) => report_failed_task(&discord_http, channel_id, &description, attempts).await.is_ok(),
+            None => false
+        };
+        if !reported {
+            eprintln!("Task {id} of guild {guild_id} failed {attempts} times and won't be executed again");
+        }
+        return
+    }
+
+    let execute_at = DateTime::from_millis(DateTime::now().timestamp_millis() + retry_delay(attempts));
+    mongodb.retry_task(id, execute_at).await.ok();
 }
 
-pub async fn execute_task(task: Task, config: GuildConfig, discord_http: Arc<Client>) {
-    let execute_in = u64::try_from(
-        task.execute_at.timestamp_millis() - DateTime::now().timestamp_millis()
-    ).unwrap_or(0); // If number is negative set it to 0 (execute it now)
-    tokio::time::sleep_until(Instant::now() + Duration::from_millis(execute_in)).await;
+async fn report_failed_task(
+    discord_http: &Arc<Client>,
+    channel_id: Id<ChannelMarker>,
+    description: &str,
+    attempts: u32
+) -> Result<(), ()> {
+    let embed = EmbedBuilder::new()
+        .title("Scheduled action failed".to_string())
+        .description(format!("{description}\nIt failed {attempts} times and won't be executed again"))
+        .to_embed();
+
+    discord_http.create_message(channel_id)
+        .embeds(&[embed]).map_err(|_| ())?
+        .await.map_err(|_| ())?;
 
-    run_action(task, config, discord_http).await.ok();
+    Ok(())
+}
+
+/// Delay in milliseconds before the next execution of a task which failed `attempts` times
+fn retry_delay(attempts: u32) -> i64 {
+    RETRY_BASE_DELAY * 2_i64.pow(attempts.saturating_sub(1).min(10))
 }
 
 pub async fn run_action(task: Task, config: GuildConfig, discord_http: Arc<Client>) -> Result<(), ()> {
@@ -74,6 +194,153 @@ pub async fn run_action(task: Task, config: GuildConfig, discord_http: Arc<Clien
         TaskAction::RemoveBan(member_id) => {
             discord_http.delete_ban(config.guild_id, member_id).await.map_err(|_| ())?;
         }
+        TaskAction::RemoveRole { member_id, role_id } => {
+            let result = discord_http.remove_guild_member_role(config.guild_id, member_id, role_id).await;
+            // roles are lost when the member leaves, the role is given back on rejoin only while the task is pending
+            if let Err(error) = result {
+                if !is_unknown_member(&error) { return Err(()) }
+            }
+        }
+        TaskAction::DeleteMessage { channel_id, message_id } => {
+            discord_http.delete_message(channel_id, message_id).await.map_err(|_| ())?;
+        }
+        TaskAction::SendMessage { channel_id, content, allowed_mentions } => {
+            discord_http.create_message(channel_id)
+                .allowed_mentions(Some(&allowed_mentions))
+                .content(&content).map_err(|_| ())?
+                .await.map_err(|_| ())?;
+        }
+        TaskAction::Remind { member_id, channel_id, content } => {
+            // the content is written by the member, so only the member can be pinged
+            let allowed_mentions = AllowedMentions { users: vec![member_id], ..Default::default() };
+            discord_http.create_message(channel_id)
+                .allowed_mentions(Some(&allowed_mentions))
+                .content(&format!("<@{member_id}> {content}")).map_err(|_| ())?
+                .await.map_err(|_| ())?;
+        }
     };
     Ok(())
-}
\ No newline at end of file
+}
+
+fn is_unknown_member(error: &twilight_http::Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorType::Response { error: ApiError::General(GeneralApiError { code: UNKNOWN_MEMBER_CODE, .. }), .. }
+    )
+}
+
+/// Rolls over `top_day` every day, `top_week` every monday and `top_month` every first day of month (at midnight UTC)
+pub async fn top_interval(
+    mongodb: MongoDBConnection,
+    redis: RedisConnection,
+    discord_http: Arc<Client>
+) {
+    loop {
+        let now = Utc::now().timestamp();
+        let next_day = (now / DAY_SECS + 1) * DAY_SECS;
+        tokio::time::sleep(Duration::from_secs((next_day - now) as u64)).await;
+
+        // 1970-01-01 was a thursday
+        let is_monday = (next_day / DAY_SECS + 3) % 7 == 0;
+
+        let is_first_day_of_month = Utc.timestamp_opt(next_day, 0)
+            .single()
+            .map(|date| date.day() == 1)
+            .unwrap_or(false);
+
+        let mut periods = vec!["day"];
+        if is_monday { periods.push("week") }
+        if is_first_day_of_month { periods.push("month") }
+
+        for period in periods {
+            let configs = ok_or_skip_without_clone!(mongodb.get_configs_with_top(period).await, Ok);
+            for config in configs {
+                rollover_top(&mongodb, &redis, &discord_http, config, period).await.ok();
+            }
+        }
+    }
+}
+
+/// Archives the ranking, posts it to the webhook and starts counting from zero
+async fn rollover_top(
+    mongodb: &MongoDBConnection,
+    redis: &RedisConnection,
+    discord_http: &Arc<Client>,
+    config: GuildConfig,
+    period: &str
+) -> Result<(), ()> {
+    let guild_id = config.guild_id;
+    let path = format!("top_{period}.{guild_id}");
+    let leaderboard = redis.start_archive(path.to_owned()).await.map_err(|_| ())?;
+    if leaderboard.is_empty() { return Ok(()) }
+
+    let ranking = leaderboard.iter()
+        .filter_map(|(user_id, messages)| {
+            Id::from_str(user_id.as_str()).ok().map(|user_id| TopArchiveEntry {
+                user_id,
+                messages: *messages
+            })
+        }).collect();
+
+    mongodb.top_archives.insert_one(TopArchive {
+        guild_id,
+        period: period.to_string(),
+        ended_at: DateTime::now(),
+        ranking
+    }, None).await.map_err(|_| ())?;
+    redis.finish_archive(path).await.map_err(|_| ())?;
+
+    if config.top.webhook_url.is_empty() { return Ok(()) }
+
+    let (webhook_id, token) = parse_webhook_url(&config.top.webhook_url).ok_or(())?;
+    let embed = EmbedBuilder::new()
+        .title(format!("Top users of {}", period_name(period)))
+        .description(leaderboard_to_string(&leaderboard[..leaderboard.len().min(10)], 0))
+        .to_embed();
+
+    discord_http.execute_webhook(webhook_id, &token)
+        .embeds(&[embed]).map_err(|_| ())?
+        .await.map_err(|_| ())?;
+
+    Ok(())
+}
+
+fn parse_webhook_url(url: &str) -> Option<(Id<WebhookMarker>, String)> {
+    let url = Url::parse(url).ok()?;
+    let mut segments = url.path_segments()?
+        .skip_while(|segment| *segment != "webhooks")
+        .skip(1);
+
+    let webhook_id = Id::from_str(segments.next()?).ok()?;
+    let token = segments.next()?.to_string();
+
+    Some((webhook_id, token))
+}
+
+#[cfg(test)]
+mod tests {
+    use twilight_model::id::Id;
+    use super::{parse_webhook_url, retry_delay};
+
+    #[test]
+    fn test_parse_webhook_url() {
+        assert_eq!(
+            parse_webhook_url("https://discord.com/api/webhooks/123/token"),
+            Some((Id::new(123), "token".to_string()))
+        );
+        assert_eq!(
+            parse_webhook_url("https://discord.com/api/v10/webhooks/123/token?wait=true"),
+            Some((Id::new(123), "token".to_string()))
+        );
+        assert_eq!(parse_webhook_url(""), None);
+        assert_eq!(parse_webhook_url("https://discord.com/api/webhooks/123"), None);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), 60 * 1000);
+        assert_eq!(retry_delay(2), 2 * 60 * 1000);
+        assert_eq!(retry_delay(4), 8 * 60 * 1000);
+        assert_eq!(retry_delay(u32::MAX), 1024 * 60 * 1000);
+    }
+}
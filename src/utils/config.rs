@@ -56,22 +56,33 @@ pub fn create_debug_config() -> GuildConfig {
                 ]),
                 logs_channel: Some(Id::new(981950096801406979)),
                 ignore: None,
+                scam_domains: vec![],
+                compiled: None
             }),
             mute_mode: MuteMode::DependOnCommand,
             mute_role: None,
             native_support: true,
             logs_channel: Some(Id::new(981950096801406979)),
             dm_case: true,
+            escalations: vec![],
+            case_rules: vec![],
+            case_managers: vec![],
         },
         premium: true,
         levels: Levels {
             xp_timeout: 30,
             xp_min: 5,
             xp_max: 5,
+            curve: Default::default(),
+            rewards: vec![],
+            rewards_mode: Default::default(),
+            announcement: None,
         },
         top: Top {
             week: true,
             day: true,
+            month: true,
+            alltime: true,
             webhook_url: String::new(),
         },
     }
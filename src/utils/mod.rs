@@ -7,6 +7,8 @@ pub mod uppercase;
 pub mod constants;
 pub mod message;
 pub mod config;
+pub mod normalize;
+pub mod urls;
 
 #[macro_export]
 macro_rules! all_macro {
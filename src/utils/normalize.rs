@@ -0,0 +1,112 @@
+/// Maximum number of the same consecutive characters kept by `normalize`
+const MAX_REPEATS: usize = 2;
+
+/// Folds the text into a form used for matching blocklisted terms, so they can't be evaded with
+/// look-alike characters, invisible characters, leetspeak or repeated letters.
+/// Runs of the same character are shortened to two, so double letters of words are kept
+pub fn normalize(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = None;
+    let mut repeats = 0;
+
+    for char in text.chars().flat_map(char::to_lowercase) {
+        if is_invisible(char) { continue }
+
+        let char = fold_leetspeak(fold_confusable(char));
+        if last == Some(char) {
+            repeats += 1;
+            if repeats >= MAX_REPEATS { continue }
+        } else { repeats = 0 }
+
+        last = Some(char);
+        result.push(char);
+    }
+
+    result
+}
+
+/// Zero-width characters and combining marks (used e.g. by zalgo text)
+fn is_invisible(char: char) -> bool {
+    matches!(
+        char,
+        '\u{00AD}' | '\u{034F}' | '\u{180E}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}' | '\u{FEFF}' | '\u{0300}'..='\u{036F}' | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}' | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+fn fold_confusable(char: char) -> char {
+    let code = char as u32;
+    match code {
+        // fullwidth forms of ASCII characters
+        0xFF01..=0xFF5E => return char::from_u32(code - 0xFEE0).unwrap_or(char).to_ascii_lowercase(),
+        // mathematical bold, italic, script, etc. letters, every alphabet has 52 letters
+        0x1D400..=0x1D6A3 => {
+            let offset = ((code - 0x1D400) % 52) as u8;
+            return (if offset < 26 { b'a' + offset } else { b'a' + offset - 26 }) as char
+        },
+        // mathematical digits, every style has 10 digits
+        0x1D7CE..=0x1D7FF => return (b'0' + ((code - 0x1D7CE) % 10) as u8) as char,
+        _ => {}
+    }
+
+    match char {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' | 'а' | 'α' | 'ɑ' => 'a',
+        'ь' | 'в' | 'β' => 'b',
+        'ç' | 'ć' | 'č' | 'с' | 'ϲ' => 'c',
+        'ď' | 'đ' | 'ԁ' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' | 'е' | 'ё' | 'ε' => 'e',
+        'ɡ' | 'ğ' => 'g',
+        'һ' | 'н' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' | 'і' | 'ї' | 'ι' => 'i',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'ł' | 'ӏ' => 'l',
+        'м' => 'm',
+        'ñ' | 'ń' | 'ň' | 'п' | 'η' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'о' | 'ο' | 'σ' => 'o',
+        'р' | 'ρ' => 'p',
+        'ř' | 'г' => 'r',
+        'ś' | 'š' | 'ş' | 'ѕ' => 's',
+        'ť' | 'т' | 'τ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'υ' => 'u',
+        'ν' => 'v',
+        'ш' | 'ω' => 'w',
+        'х' | 'χ' => 'x',
+        'ý' | 'ÿ' | 'у' | 'γ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        _ => char
+    }
+}
+
+/// Characters like `!` are commonly used between words, so they aren't folded
+fn fold_leetspeak(char: char) -> char {
+    match char {
+        '0' => 'o',
+        '1' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        '8' => 'b',
+        _ => char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::normalize::normalize;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Hello"), "hello");
+        assert_eq!(normalize("h\u{200B}e\u{200D}llo"), "hello");
+        assert_eq!(normalize("һеllо"), "hello");
+        assert_eq!(normalize("Ｈｅｌｌｏ"), "hello");
+        assert_eq!(normalize("𝐡𝐞𝐥𝐥𝐨"), "hello");
+        assert_eq!(normalize("h3ll0"), "hello");
+        assert_eq!(normalize("heeeeellllooo"), "heelloo");
+        assert_eq!(normalize("h̸̢e̵l̷l̴o̶"), "hello");
+        assert_eq!(normalize("as"), "as");
+    }
+}
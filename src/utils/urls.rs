@@ -0,0 +1,63 @@
+use std::sync::OnceLock;
+use reqwest::Url;
+
+static MARKDOWN_LINK_REGEX: OnceLock<regex::Regex> = OnceLock::new();
+static URL_REGEX: OnceLock<regex::Regex> = OnceLock::new();
+
+/// Finds URLs in bare form and in markdown links, where only the target of the link is used
+pub fn extract_urls(content: &str) -> Vec<Url> {
+    let markdown_links = MARKDOWN_LINK_REGEX.get_or_init(|| regex::Regex::new(
+        r"\[[^\]]*\]\(\s*<?(https?://[^\s)>]+)"
+    ).expect("Invalid markdown link regex"));
+    let urls = URL_REGEX.get_or_init(|| regex::Regex::new(
+        r#"(?i)https?://[^\s<>()\[\]"'`|]+"#
+    ).expect("Invalid url regex"));
+
+    let mut result = Vec::new();
+    let targets = markdown_links.captures_iter(content).filter_map(|captures| captures.get(1));
+    for url in targets.chain(urls.find_iter(content)) {
+        // punctuation after a bare URL most likely ends the sentence
+        let url = url.as_str().trim_end_matches(['.', ',', '!', '?', ':', ';', '*', '_', '~']);
+        let Ok(url) = Url::parse(url) else { continue };
+        if url.host_str().is_some() && !result.contains(&url) { result.push(url) }
+    }
+
+    result
+}
+
+/// Lowercase host of the URL without the trailing dot
+pub fn domain(url: &Url) -> Option<String> {
+    url.host_str().map(|host| host.trim_end_matches('.').to_lowercase())
+}
+
+/// Whether the domain is the `pattern` domain or any of its subdomains
+pub fn matches_domain(domain: &str, pattern: &str) -> bool {
+    let pattern = pattern.trim_end_matches('.');
+    domain.strip_suffix(pattern).map(|prefix| prefix.is_empty() || prefix.ends_with('.')).unwrap_or(false)
+        && !pattern.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::urls::{domain, extract_urls, matches_domain};
+
+    #[test]
+    fn test_extract_urls() {
+        let domains = |content: &str| extract_urls(content).iter().filter_map(domain).collect::<Vec<String>>();
+
+        assert_eq!(domains("see https://Example.com/path."), vec!["example.com"]);
+        assert_eq!(domains("[google.com](https://evil.com/login)"), vec!["evil.com"]);
+        assert_eq!(domains("[text](<https://a.com>) and <http://b.com>"), vec!["a.com", "b.com"]);
+        assert_eq!(domains("**https://a.com/** https://a.com/"), vec!["a.com"]);
+        assert_eq!(domains("no links, just example.com"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_matches_domain() {
+        assert!(matches_domain("example.com", "example.com"));
+        assert!(matches_domain("cdn.example.com", "example.com"));
+        assert!(!matches_domain("notexample.com", "example.com"));
+        assert!(!matches_domain("example.com", "cdn.example.com"));
+        assert!(!matches_domain("example.com", ""));
+    }
+}